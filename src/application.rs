@@ -7,7 +7,6 @@ use log::{debug, info};
 use adw::subclass::prelude::*;
 use glib::clone;
 use gtk::prelude::*;
-use gtk::subclass::prelude::*;
 use gtk::{gdk, gio, glib};
 
 use crate::config::{APP_ID, PKGDATADIR, PROFILE, VERSION};
@@ -104,6 +103,13 @@ impl RamshApplication {
             app.show_about_dialog();
         }));
         self.add_action(&action_about);
+
+        // Benchmark
+        let action_benchmark = gio::SimpleAction::new("benchmark", None);
+        action_benchmark.connect_activate(clone!(@weak self as app => move |_, _| {
+            app.main_window().show_benchmark();
+        }));
+        self.add_action(&action_benchmark);
     }
 
     // Sets up keyboard shortcuts
@@ -0,0 +1,994 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Headless front-end to the cracking engine, for machines without a display.
+//!
+//! Exit codes: 0 when every passphrase was found, 1 when the candidates ran
+//! out first and 2 on any error.
+
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use ramsh::engine::{
+    self, collect_candidates, count_candidates, format_duration, format_hash_lines,
+    format_passphrase, read_hash_lines, Attack, Benchmark, CandidateFilter, CandidateSource,
+    Checkpoint, Checkpointer, Combinator, Cracker, FileIdentity, Hybrid, HybridSide, Mangled,
+    MaskCandidates, MaskSpec, NetworkParams, Outcome, Pbkdf2Backend, PmkidTarget, Potfile, RuleSet,
+    SkipStats, Wordlist, WordlistQueue, BENCHMARK_STEP, CUSTOM_CHARSETS, MAX_PASSPHRASE_LEN,
+    MIN_PASSPHRASE_LEN, STATUS_INTERVAL,
+};
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const EXIT_FOUND: u8 = 0;
+const EXIT_EXHAUSTED: u8 = 1;
+const EXIT_ERROR: u8 = 2;
+/// What shells report for a command stopped by SIGINT.
+const EXIT_CANCELLED: u8 = 130;
+
+/// How often the session file of `--session` is written while running.
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Wordlist progress is in bytes, the progress of the other attacks in
+/// candidates.
+const WORDLIST_TEMPLATE: &str =
+    "{spinner} [{elapsed_precise}] {wide_bar} {bytes}/{total_bytes} ({bytes_per_sec}, ETA {eta}) {msg}";
+const CANDIDATE_TEMPLATE: &str =
+    "{spinner} [{elapsed_precise}] {wide_bar} {pos}/{len} ({per_sec}, ETA {eta}) {msg}";
+
+const USAGE: &str = "\
+Usage: ramsh-cli [OPTIONS] <TARGET> <WORDLIST>...
+       ramsh-cli [OPTIONS] --mask <MASK> <TARGET>
+       ramsh-cli [OPTIONS] --append-mask <MASK> <TARGET> <WORDLIST>...
+       ramsh-cli [OPTIONS] --combine <RIGHT> <TARGET> <LEFT>
+       ramsh-cli [OPTIONS] --restore <FILE>
+       ramsh-cli --export <FILE> <TARGET>
+       ramsh-cli [--backend <NAME>] --benchmark
+
+Arguments:
+  <TARGET>       A network JSON file (essid, bssid, sta_mac, pmkid), a file
+                 of hash lines, one target each, or a single hash line.
+                 Hash lines are in hashcat's 22000 and 22001 format,
+                   WPA*01*PMKID*MAC_AP*MAC_STA*ESSID_HEX***
+                 or its older 16800 one,
+                   PMKID*MAC_AP*MAC_STA*ESSID_HEX
+  <WORDLIST>...  Wordlist files, one candidate per line, optionally compressed
+                 with gzip, bzip2, xz or zstd. They are tried in order.
+
+Options:
+  -r, --rules <FILE>       Mangle every candidate with a hashcat-style rule file
+  -m, --mask <MASK>        Brute-force the candidates matching a mask, e.g.
+                           ?l?l?l?l?d?d?d?d, instead of reading wordlists
+      --append-mask <MASK> Append every candidate of a mask to each line
+      --prepend-mask <MASK>
+                           Prepend every candidate of a mask to each line
+  -c, --combine <RIGHT>    Join every line of the wordlist with every line of
+                           RIGHT, which is kept in memory
+      --separator <SEP>    Put SEP between the joined lines
+      --left-rules <FILE>  Mangle the lines of the wordlist before joining
+      --right-rules <FILE> Mangle the lines of RIGHT before joining
+  -1, -2, -3, -4 <CHARSET> Custom charsets for ?1 to ?4, e.g. ?l?u
+  -i, --increment          Also try the mask cut down to shorter lengths
+      --increment-min <N>  Shortest length to increment from [default: 1]
+      --increment-max <N>  Longest length to increment to [default: mask]
+      --dedupe             Skip candidates already tried, keeping them all in
+                           memory
+      --raw-psk            Take 64 hex digit candidates as raw PSKs
+      --potfile-path <FILE>
+                           Look up and record passphrases in FILE
+                           [default: ~/.local/share/ramsh/ramsh.potfile]
+      --potfile-disable    Neither look up nor record passphrases
+      --backend <NAME>     Derive PMKs with scalar, sse2, sha-ni, avx2 or
+                           avx512 [default: the fastest this CPU supports]
+      --session <FILE>     Save the progress to FILE every 30 s and when
+                           interrupted, to be resumed with --restore
+      --restore <FILE>     Resume the run saved in FILE, with its targets,
+                           attack and filter, saving it there again
+  -q, --quiet              Don't show a progress bar
+      --export <FILE>      Write the targets to FILE as 16800 hash lines,
+                           or to stdout if FILE is -, then exit
+      --benchmark          Measure how many PMKs per second this machine
+                           derives, then exit
+  -h, --help               Print this help and exit
+  -V, --version            Print the version and exit
+
+Mask charsets: ?l lower, ?u upper, ?d digits, ?s specials, ?a all of those,
+?h and ?H lower and upper hex, ?b every byte, ?? a literal '?'.
+
+Candidates shorter than 8 or longer than 63 bytes, or with control
+characters, are never tried.
+
+With several targets, each one found is printed as HASH_LINE:PASSPHRASE, and
+the PMK of a candidate is only derived once for every target of an ESSID.
+
+Exit status: 0 if every target was found, 1 if the candidates ran out, 2 on
+error, 130 if interrupted with Ctrl+C.";
+
+struct Args {
+    target: String,
+    wordlists: Vec<String>,
+    rules: Option<String>,
+    mask: Option<MaskSpec>,
+    /// Combine the wordlists with the mask instead of using it alone.
+    hybrid: Option<HybridSide>,
+    combinator: Option<CombinatorArgs>,
+    filter: CandidateFilter,
+    /// Where recovered passphrases are kept, unless disabled.
+    potfile: Option<PathBuf>,
+    backend: Pbkdf2Backend,
+    quiet: bool,
+    /// Where the progress is saved, if anywhere.
+    session: Option<PathBuf>,
+    /// Take the targets, attack and filter from the session file instead,
+    /// and carry on from where it stopped.
+    restore: bool,
+}
+
+/// The right side of a combinator attack, the wordlist being the left.
+struct CombinatorArgs {
+    right: String,
+    separator: String,
+    left_rules: Option<String>,
+    right_rules: Option<String>,
+}
+
+enum Command {
+    Crack(Box<Args>),
+    Benchmark(Pbkdf2Backend),
+    /// Write the targets to a file, or to stdout for `-`.
+    Export {
+        target: String,
+        path: String,
+    },
+    Help,
+    Version,
+}
+
+fn parse_args() -> Result<Command, String> {
+    let mut quiet = false;
+    let mut benchmark = false;
+    let mut export = None;
+    let mut session = None;
+    let mut restore = None;
+    let mut backend = Pbkdf2Backend::detect();
+    let mut potfile = Some(engine::potfile_path());
+    let mut filter = CandidateFilter::default();
+    let mut rules = None;
+    let mut mask = None;
+    let mut hybrid = None;
+    let mut right = None;
+    let mut separator = String::new();
+    let mut left_rules = None;
+    let mut right_rules = None;
+    let mut charsets = vec![String::new(); CUSTOM_CHARSETS];
+    let mut increment = false;
+    let mut increment_min = 1;
+    let mut increment_max = MAX_PASSPHRASE_LEN;
+    let mut positional = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "--benchmark" => benchmark = true,
+            "-q" | "--quiet" => quiet = true,
+            "--export" => {
+                export = Some(
+                    args.next()
+                        .ok_or_else(|| format!("missing file for '{}'", arg))?,
+                )
+            }
+            "--session" | "--restore" => {
+                let path = PathBuf::from(
+                    args.next()
+                        .ok_or_else(|| format!("missing file for '{}'", arg))?,
+                );
+                if arg == "--session" {
+                    session = Some(path);
+                } else {
+                    restore = Some(path);
+                }
+            }
+            "-r" | "--rules" => {
+                rules = Some(
+                    args.next()
+                        .ok_or_else(|| format!("missing file for '{}'", arg))?,
+                )
+            }
+            "-m" | "--mask" => {
+                mask = Some(
+                    args.next()
+                        .ok_or_else(|| format!("missing mask for '{}'", arg))?,
+                );
+                hybrid = None;
+            }
+            "--append-mask" | "--prepend-mask" => {
+                mask = Some(
+                    args.next()
+                        .ok_or_else(|| format!("missing mask for '{}'", arg))?,
+                );
+                hybrid = Some(if arg == "--append-mask" {
+                    HybridSide::Append
+                } else {
+                    HybridSide::Prepend
+                });
+            }
+            "-c" | "--combine" => {
+                right = Some(
+                    args.next()
+                        .ok_or_else(|| format!("missing file for '{}'", arg))?,
+                )
+            }
+            "--separator" => {
+                separator = args
+                    .next()
+                    .ok_or_else(|| format!("missing separator for '{}'", arg))?
+            }
+            "--left-rules" | "--right-rules" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| format!("missing file for '{}'", arg))?;
+                if arg == "--left-rules" {
+                    left_rules = Some(path);
+                } else {
+                    right_rules = Some(path);
+                }
+            }
+            "-1" | "-2" | "-3" | "-4" => {
+                let index = arg[1..].parse::<usize>().unwrap() - 1;
+                charsets[index] = args
+                    .next()
+                    .ok_or_else(|| format!("missing charset for '{}'", arg))?;
+            }
+            "-i" | "--increment" => increment = true,
+            "--increment-min" | "--increment-max" => {
+                let length = args
+                    .next()
+                    .and_then(|length| length.parse().ok())
+                    .ok_or_else(|| format!("missing or invalid length for '{}'", arg))?;
+                if arg == "--increment-min" {
+                    increment_min = length;
+                } else {
+                    increment_max = length;
+                }
+            }
+            "--dedupe" => filter.dedupe = true,
+            "--raw-psk" => filter.raw_psk = true,
+            "--potfile-path" => {
+                potfile = Some(PathBuf::from(
+                    args.next()
+                        .ok_or_else(|| format!("missing file for '{}'", arg))?,
+                ))
+            }
+            "--potfile-disable" => potfile = None,
+            "--backend" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| format!("missing backend for '{}'", arg))?;
+                backend = Pbkdf2Backend::from_name(&name)
+                    .ok_or_else(|| format!("unknown backend '{}'", name))?;
+                if !backend.is_supported() {
+                    return Err(format!("{} isn't supported by this CPU", backend));
+                }
+            }
+            flag if flag.starts_with('-') && flag.len() > 1 => {
+                return Err(format!("unknown option '{}'", flag))
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    if benchmark {
+        return Ok(Command::Benchmark(backend));
+    }
+    if let Some(path) = export {
+        if positional.len() != 1 {
+            return Err(String::from("expected a single target with --export"));
+        }
+        return Ok(Command::Export {
+            target: positional.remove(0),
+            path,
+        });
+    }
+
+    if let Some(path) = restore {
+        if !positional.is_empty() {
+            return Err(String::from(
+                "--restore takes the targets and wordlists from the session",
+            ));
+        }
+        return Ok(Command::Crack(Box::new(Args {
+            target: path.display().to_string(),
+            wordlists: Vec::new(),
+            rules: None,
+            mask: None,
+            hybrid: None,
+            combinator: None,
+            filter,
+            potfile,
+            backend,
+            quiet,
+            session: Some(path),
+            restore: true,
+        })));
+    }
+
+    let mask = mask.map(|mask| MaskSpec {
+        mask,
+        charsets,
+        increment: increment.then_some(increment_min..=increment_max),
+    });
+    match (&mask, hybrid, positional.len()) {
+        (None, _, 0 | 1) | (Some(_), Some(_), 0 | 1) => {
+            return Err(String::from("expected a target and at least one wordlist"))
+        }
+        (Some(_), None, 0) => return Err(String::from("expected a target")),
+        (Some(_), None, 2..) => {
+            return Err(String::from(
+                "wordlists can't be used with --mask, see --append-mask",
+            ))
+        }
+        _ => (),
+    }
+    if mask.is_some() && rules.is_some() {
+        return Err(String::from("rules can't be used with a mask"));
+    }
+
+    let combinator = right.map(|right| CombinatorArgs {
+        right,
+        separator,
+        left_rules,
+        right_rules,
+    });
+    if combinator.is_some() {
+        if mask.is_some() {
+            return Err(String::from("a mask can't be used with --combine"));
+        }
+        if rules.is_some() {
+            return Err(String::from(
+                "--rules can't be used with --combine, see --left-rules",
+            ));
+        }
+        if positional.len() != 2 {
+            return Err(String::from(
+                "expected a target and a single wordlist with --combine",
+            ));
+        }
+    }
+
+    let target = positional.remove(0);
+    Ok(Command::Crack(Box::new(Args {
+        target,
+        wordlists: positional,
+        rules,
+        mask,
+        hybrid,
+        combinator,
+        filter,
+        potfile,
+        backend,
+        quiet,
+        session,
+        restore: false,
+    })))
+}
+
+/// Fills `args` in from the session file of `--restore`, checking that its
+/// files haven't changed, and returns the checkpoint to carry on from.
+fn restore_args(args: &mut Args) -> Result<Checkpoint, String> {
+    let path = args.session.clone().unwrap();
+    let checkpoint = match Checkpoint::load(&path) {
+        Ok(Some(checkpoint)) => checkpoint,
+        Ok(None) => return Err(format!("{}: no such session", path.display())),
+        Err(err) => return Err(format!("{}: {}", path.display(), err)),
+    };
+    match checkpoint.verify() {
+        Ok(true) => (),
+        Ok(false) => {
+            return Err(format!(
+                "{}: a file has changed since the session was saved",
+                path.display()
+            ))
+        }
+        Err(err) => return Err(format!("{}: {}", path.display(), err)),
+    }
+
+    let name = |file: &FileIdentity| file.path.display().to_string();
+    args.filter = checkpoint.filter;
+    match &checkpoint.attack {
+        Attack::Wordlist { wordlists, rules } => {
+            args.wordlists = wordlists.iter().map(name).collect();
+            args.rules = rules.as_ref().map(name);
+        }
+        Attack::Mask { mask } => args.mask = Some(mask.clone()),
+        Attack::Hybrid {
+            wordlists,
+            mask,
+            side,
+        } => {
+            args.wordlists = wordlists.iter().map(name).collect();
+            args.mask = Some(mask.clone());
+            args.hybrid = Some(*side);
+        }
+        Attack::Combinator {
+            left,
+            right,
+            separator,
+            left_rules,
+            right_rules,
+        } => {
+            args.wordlists = vec![name(left)];
+            args.combinator = Some(CombinatorArgs {
+                right: name(right),
+                separator: separator.clone(),
+                left_rules: left_rules.as_ref().map(name),
+                right_rules: right_rules.as_ref().map(name),
+            });
+        }
+    }
+    Ok(checkpoint)
+}
+
+/// Describes the attack of `args` for a session file, identifying its files
+/// as they are now.
+fn session_attack(args: &Args) -> Result<Attack, String> {
+    // Made absolute, so that the session can be resumed from anywhere
+    let identify = |path: &String| {
+        fs::canonicalize(path)
+            .and_then(|path| FileIdentity::of(&path))
+            .map_err(|err| format!("{}: {}", path, err))
+    };
+    let identify_all = |paths: &[String]| paths.iter().map(identify).collect::<Result<_, _>>();
+    Ok(match (&args.combinator, &args.mask, args.hybrid) {
+        (Some(combinator), _, _) => Attack::Combinator {
+            left: identify(&args.wordlists[0])?,
+            right: identify(&combinator.right)?,
+            separator: combinator.separator.clone(),
+            left_rules: combinator.left_rules.as_ref().map(identify).transpose()?,
+            right_rules: combinator.right_rules.as_ref().map(identify).transpose()?,
+        },
+        (None, Some(mask), Some(side)) => Attack::Hybrid {
+            wordlists: identify_all(&args.wordlists)?,
+            mask: mask.clone(),
+            side,
+        },
+        (None, Some(mask), None) => Attack::Mask { mask: mask.clone() },
+        (None, None, _) => Attack::Wordlist {
+            wordlists: identify_all(&args.wordlists)?,
+            rules: args.rules.as_ref().map(identify).transpose()?,
+        },
+    })
+}
+
+/// Reads the targets from a JSON network file, a file of hash lines, or a
+/// single hash line.
+fn load_targets(arg: &str) -> Result<Vec<PmkidTarget>, String> {
+    let path = Path::new(arg);
+    if !path.is_file() {
+        // Hash lines are split by '*' and never have a '/', so anything else
+        // was meant as a file
+        if !arg.contains('*') || arg.contains(std::path::MAIN_SEPARATOR) {
+            return Err(match fs::metadata(path) {
+                Ok(_) => format!("{}: not a file", path.display()),
+                Err(err) => format!("{}: {}", path.display(), err),
+            });
+        }
+        return PmkidTarget::from_hash_line(arg)
+            .map(|target| vec![target])
+            .map_err(|err| err.to_string());
+    }
+
+    let contents =
+        fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    if contents.trim_start().starts_with('{') {
+        let params = NetworkParams::from_reader(contents.as_bytes())
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+        return params
+            .to_target()
+            .map(|target| vec![target])
+            .map_err(|err| err.to_string());
+    }
+
+    let (targets, errors) = read_hash_lines(&contents);
+    for err in &errors {
+        eprintln!(
+            "ramsh-cli: {}:{}: {}, skipping",
+            path.display(),
+            err.line,
+            err.error
+        );
+    }
+    if targets.is_empty() {
+        return Err(if errors.is_empty() {
+            format!("{}: no hash line found", path.display())
+        } else {
+            format!("{}: no valid hash lines", path.display())
+        });
+    }
+    Ok(targets)
+}
+
+/// Runs `cracker` on its own thread, following its status on `bar` until
+/// it is done. `sources` are the names of the sources in the queue.
+fn run_with_bar<S>(
+    cracker: &Cracker,
+    source: S,
+    bar: &ProgressBar,
+    sources: &[String],
+) -> io::Result<Outcome>
+where
+    S: CandidateSource,
+{
+    let status = cracker.status_handle();
+    thread::scope(|scope| {
+        // Dropped when the run ends, whichever way it does
+        let (running, stopped) = mpsc::channel::<()>();
+        let run = scope.spawn(move || {
+            let _running = running;
+            cracker.run(source)
+        });
+
+        let targets = cracker.targets().len();
+        let mut shown = None;
+        while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(STATUS_INTERVAL) {
+            let status = status.snapshot();
+            bar.set_position(status.done);
+            let found = status.found.iter().flatten().count();
+            if shown != Some((status.source, found)) {
+                shown = Some((status.source, found));
+                let mut message = Vec::new();
+                if sources.len() > 1 {
+                    message.push(format!(
+                        "{} ({}/{})",
+                        sources[status.source],
+                        status.source + 1,
+                        sources.len()
+                    ));
+                }
+                if targets > 1 {
+                    message.push(format!("{}/{} found", found, targets));
+                }
+                bar.set_message(message.join(", "));
+            }
+        }
+        run.join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+fn open_wordlist(path: &str) -> Result<Wordlist, String> {
+    Wordlist::open(Path::new(path)).map_err(|err| format!("{}: {}", path, err))
+}
+
+/// Queues every pairing of the lines of `left` and `combinator.right`.
+fn queue_combinator(
+    queue: &mut WordlistQueue,
+    left: &str,
+    combinator: &CombinatorArgs,
+) -> Result<(), String> {
+    let load = |path: &Option<String>| {
+        path.as_deref()
+            .map(load_rules)
+            .transpose()
+            .map(|rules| rules.map(Arc::new))
+    };
+    let left_rules = load(&combinator.left_rules)?;
+    let right_rules = load(&combinator.right_rules)?;
+
+    let path = &combinator.right;
+    let right = open_wordlist(path)?;
+    let right = match &right_rules {
+        Some(rules) => collect_candidates(Mangled::new(right, rules.clone(), 0)),
+        None => collect_candidates(right),
+    }
+    .map_err(|err| format!("{}: {}", path, err))?;
+    if right.is_empty() {
+        return Err(format!("{}: no candidates", path));
+    }
+
+    // Progress counts pairings, so the left words have to be counted first
+    let words = match &left_rules {
+        Some(rules) => count_candidates(Mangled::new(open_wordlist(left)?, rules.clone(), 0)),
+        None => open_wordlist(left)?.count_lines(),
+    }
+    .map_err(|err| format!("{}: {}", left, err))?;
+
+    let separator = combinator.separator.clone().into_bytes();
+    let wordlist = open_wordlist(left)?;
+    match left_rules {
+        Some(rules) => queue.push(Combinator::new(
+            Mangled::new(wordlist, rules, 0),
+            words,
+            right,
+            separator,
+        )),
+        None => queue.push(Combinator::new(wordlist, words, right, separator)),
+    }
+    Ok(())
+}
+
+/// Loads a rule file, warning about the lines that had to be skipped.
+fn load_rules(path: &str) -> Result<RuleSet, String> {
+    let rules = RuleSet::load(Path::new(path)).map_err(|err| format!("{}: {}", path, err))?;
+    for (line, err) in rules.invalid() {
+        eprintln!("ramsh-cli: {}:{}: {}, skipping", path, line, err);
+    }
+    if rules.is_empty() {
+        return Err(format!("{}: no valid rules", path));
+    }
+    Ok(rules)
+}
+
+fn report_skipped(skipped: &SkipStats) {
+    if skipped.total() > 0 {
+        eprintln!("{}", skipped);
+    }
+}
+
+fn crack(mut args: Args) -> Result<u8, String> {
+    let restored = match args.restore {
+        true => Some(restore_args(&mut args)?),
+        false => None,
+    };
+    let targets = match &restored {
+        Some(checkpoint) => checkpoint.targets.clone(),
+        None => load_targets(&args.target)?,
+    };
+    let mut cracker = Cracker::with_targets(targets.clone())
+        .map_err(|err| format!("{}: {}", args.target, err))?;
+    cracker.set_filter(args.filter);
+    cracker.set_backend(args.backend);
+    if let Some(path) = &args.potfile {
+        let potfile =
+            Potfile::load(path.clone()).map_err(|err| format!("{}: {}", path.display(), err))?;
+        if potfile.skipped() > 0 {
+            eprintln!(
+                "ramsh-cli: {}: skipping lines that aren't PMKID targets ({})",
+                path.display(),
+                potfile.skipped()
+            );
+        }
+        cracker.set_potfile(Arc::new(potfile));
+    }
+    // No need to open anything for targets that are already solved
+    if let Some(outcome) = cracker.recall() {
+        return report(&cracker, outcome);
+    }
+
+    let rules = args
+        .rules
+        .as_deref()
+        .map(load_rules)
+        .transpose()?
+        .map(Arc::new);
+    let masks = args
+        .mask
+        .as_ref()
+        .map(|spec| {
+            match args.hybrid {
+                Some(_) => spec.masks(),
+                None => {
+                    // Like hashcat, say which lengths of the increment don't
+                    // make passphrases rather than leave them out quietly
+                    let masks = spec.passphrase_masks();
+                    let skipped = spec.too_short_lengths().unwrap_or_default();
+                    if let (Ok(_), Some(first), Some(last)) =
+                        (&masks, skipped.first(), skipped.last())
+                    {
+                        eprintln!(
+                            "Skipping mask lengths {} to {}, passphrases are at least {} characters",
+                            first, last, MIN_PASSPHRASE_LEN
+                        );
+                    }
+                    masks
+                }
+            }
+            .map_err(|err| format!("{}: {}", spec.mask, err))
+        })
+        .transpose()?;
+
+    let mut queue = WordlistQueue::new();
+    let mut sources = Vec::new();
+    let template = match (masks, args.hybrid) {
+        _ if args.combinator.is_some() => {
+            let combinator = args.combinator.as_ref().unwrap();
+            let left = &args.wordlists[0];
+            queue_combinator(&mut queue, left, combinator)?;
+            sources.push(left.clone());
+            CANDIDATE_TEMPLATE
+        }
+        (Some(masks), Some(side)) => {
+            // Progress counts candidates, so the words have to be counted first
+            let mut words = Vec::new();
+            for path in &args.wordlists {
+                let count = open_wordlist(path)?
+                    .count_lines()
+                    .map_err(|err| format!("{}: {}", path, err))?;
+                words.push(count);
+            }
+
+            let lengths = masks.len();
+            for mask in masks {
+                for (path, &words) in args.wordlists.iter().zip(&words) {
+                    sources.push(if lengths > 1 {
+                        format!("{}, mask length {}", path, mask.len())
+                    } else {
+                        path.clone()
+                    });
+                    queue.push(Hybrid::new(open_wordlist(path)?, mask.clone(), side, words));
+                }
+            }
+            CANDIDATE_TEMPLATE
+        }
+        (Some(masks), None) => {
+            for mask in masks {
+                sources.push(format!("length {}", mask.len()));
+                queue.push(MaskCandidates::new(mask));
+            }
+            CANDIDATE_TEMPLATE
+        }
+        (None, _) => match &rules {
+            Some(rules) => {
+                // Progress counts candidates, so the words have to be counted
+                // first
+                for path in &args.wordlists {
+                    let words = open_wordlist(path)?
+                        .count_lines()
+                        .map_err(|err| format!("{}: {}", path, err))?;
+                    queue.push(Mangled::new(open_wordlist(path)?, rules.clone(), words));
+                    sources.push(path.clone());
+                }
+                CANDIDATE_TEMPLATE
+            }
+            None => {
+                for path in &args.wordlists {
+                    queue.push(open_wordlist(path)?);
+                    sources.push(path.clone());
+                }
+                WORDLIST_TEMPLATE
+            }
+        },
+    };
+
+    // The progress is saved as the run goes, and once more if it is
+    // interrupted
+    let checkpoint = match restored {
+        Some(checkpoint) => Some(checkpoint),
+        None if args.session.is_some() => Some(Checkpoint::new(targets, session_attack(&args)?)),
+        None => None,
+    };
+    let checkpointer = args
+        .session
+        .clone()
+        .zip(checkpoint)
+        .map(|(path, mut checkpoint)| {
+            checkpoint.filter = args.filter;
+            Arc::new(Checkpointer::new(path, checkpoint, CHECKPOINT_INTERVAL))
+        });
+    if let Some(checkpointer) = &checkpointer {
+        cracker.set_checkpointer(checkpointer.clone());
+    }
+
+    // Ctrl+C stops the run at the next batch, a second one right away
+    let cancel = cracker.cancel_token();
+    ctrlc::set_handler(move || {
+        if cancel.is_cancelled() {
+            std::process::exit(EXIT_CANCELLED.into());
+        }
+        cancel.cancel();
+    })
+    .map_err(|err| err.to_string())?;
+
+    // Wordlist progress is in bytes, so only the other attacks can be
+    // estimated before they start
+    if !args.quiet && template == CANDIDATE_TEMPLATE {
+        if let Ok(Some(benchmark)) = Benchmark::load(&engine::benchmark_path()) {
+            if let Some(estimate) = benchmark.estimate(queue.total()) {
+                eprintln!(
+                    "{} candidates, about {} at {:.0} PMK/s",
+                    queue.total(),
+                    format_duration(estimate),
+                    benchmark.rate()
+                );
+            }
+        }
+    }
+
+    let bar = if args.quiet {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::with_draw_target(queue.total(), ProgressDrawTarget::stderr())
+    };
+    bar.set_style(
+        ProgressStyle::default_bar()
+            .template(template)
+            .progress_chars("=> "),
+    );
+
+    let outcome = run_with_bar(&cracker, queue, &bar, &sources);
+    bar.finish_and_clear();
+    let outcome = outcome.map_err(|err| match &args.wordlists[..] {
+        [wordlist] => format!("{}: {}", wordlist, err),
+        _ => err.to_string(),
+    })?;
+    if let (Outcome::Cancelled { .. }, Some(checkpointer)) = (&outcome, &checkpointer) {
+        let path = args.session.as_ref().unwrap();
+        checkpointer
+            .save()
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+        eprintln!(
+            "Saved the session, resume it with --restore {}",
+            path.display()
+        );
+    }
+    report(&cracker, outcome)
+}
+
+/// Prints how a run ended, returning the exit status it makes for.
+///
+/// A single passphrase is printed on its own, several as potfile lines so
+/// that it is clear which target each one belongs to.
+fn report(cracker: &Cracker, outcome: Outcome) -> Result<u8, String> {
+    let targets = cracker.targets();
+    let print_found = |outcome: &Outcome| {
+        for (index, target) in targets.iter().enumerate() {
+            match outcome.passphrase(index) {
+                Some(passphrase) if targets.len() == 1 => {
+                    println!("{}", format_passphrase(passphrase))
+                }
+                Some(passphrase) => println!(
+                    "{}:{}",
+                    target.to_hash_line(),
+                    format_passphrase(passphrase)
+                ),
+                None => (),
+            }
+        }
+    };
+
+    match &outcome {
+        Outcome::Found {
+            elapsed,
+            skipped,
+            from_potfile,
+            ..
+        } => {
+            if *from_potfile {
+                eprintln!("Already in the potfile");
+            } else {
+                report_skipped(skipped);
+                eprintln!("Found in {} ms", elapsed.as_millis());
+            }
+            print_found(&outcome);
+            Ok(EXIT_FOUND)
+        }
+        Outcome::Exhausted {
+            found,
+            elapsed,
+            skipped,
+            ..
+        } => {
+            report_skipped(skipped);
+            if targets.len() == 1 {
+                eprintln!("No match found after {} ms", elapsed.as_millis());
+            } else {
+                eprintln!(
+                    "Found {} of {} after {} ms",
+                    found.iter().flatten().count(),
+                    targets.len(),
+                    elapsed.as_millis()
+                );
+            }
+            print_found(&outcome);
+            Ok(EXIT_EXHAUSTED)
+        }
+        Outcome::Cancelled {
+            found,
+            elapsed,
+            tried,
+        } => {
+            eprintln!(
+                "Interrupted after {} ms and {} candidates, {} of {} found",
+                elapsed.as_millis(),
+                tried,
+                found.iter().flatten().count(),
+                targets.len()
+            );
+            print_found(&outcome);
+            Ok(EXIT_CANCELLED)
+        }
+    }
+}
+
+/// Writes the targets of `target` as 16800 hash lines to `path`.
+fn export(target: &str, path: &str) -> Result<(), String> {
+    let targets = load_targets(target)?;
+    let lines = format_hash_lines(&targets);
+    if path == "-" {
+        print!("{}", lines);
+    } else {
+        fs::write(path, lines).map_err(|err| format!("{}: {}", path, err))?;
+        eprintln!("Exported {} targets to {}", targets.len(), path);
+    }
+    Ok(())
+}
+
+/// Runs a benchmark, printing and saving the result.
+fn benchmark(backend: Pbkdf2Backend) -> ExitCode {
+    eprintln!(
+        "Benchmarking {} for {} s per thread count...",
+        backend,
+        BENCHMARK_STEP.as_secs()
+    );
+    let benchmark = match Benchmark::run(BENCHMARK_STEP, backend) {
+        Ok(benchmark) => benchmark,
+        Err(err) => {
+            eprintln!("ramsh-cli: {}", err);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+
+    println!(
+        "{:>7}  {:>10}  {:>16}",
+        "Threads", "PMK/s", "PMK/s per thread"
+    );
+    for point in &benchmark.points {
+        println!(
+            "{:>7}  {:>10.1}  {:>16.1}",
+            point.threads,
+            point.rate,
+            point.rate_per_thread()
+        );
+    }
+
+    let path = engine::benchmark_path();
+    if let Err(err) = benchmark.save(&path) {
+        eprintln!("ramsh-cli: {}: {}", path.display(), err);
+        return ExitCode::from(EXIT_ERROR);
+    }
+    ExitCode::SUCCESS
+}
+
+fn main() -> ExitCode {
+    pretty_env_logger::init();
+
+    let args = match parse_args() {
+        Ok(Command::Crack(args)) => *args,
+        Ok(Command::Benchmark(backend)) => return benchmark(backend),
+        Ok(Command::Export { target, path }) => {
+            return match export(&target, &path) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(err) => {
+                    eprintln!("ramsh-cli: {}", err);
+                    ExitCode::from(EXIT_ERROR)
+                }
+            }
+        }
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Version) => {
+            println!("ramsh-cli {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("ramsh-cli: {}\n\n{}", err, USAGE);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+
+    match crack(args) {
+        Ok(status) => ExitCode::from(status),
+        Err(err) => {
+            eprintln!("ramsh-cli: {}", err);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
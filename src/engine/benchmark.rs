@@ -0,0 +1,149 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use rayon::prelude::*;
+use rayon::ThreadPoolBuildError;
+use serde::{Deserialize, Serialize};
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use super::cracker::Found;
+use super::{save_json, Cracker, Pbkdf2Backend, PmkidTarget};
+
+/// The candidate tried over and over. It never matches, so that every try
+/// goes through the whole derivation.
+const CANDIDATE: &[u8] = b"benchmark";
+
+/// How long a [`Benchmark`] spends on each number of threads by default.
+pub const BENCHMARK_STEP: Duration = Duration::from_secs(2);
+
+/// The throughput measured with a given number of threads.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkPoint {
+    pub threads: usize,
+    /// PMKs derived and checked per second, over all threads.
+    pub rate: f64,
+}
+
+impl BenchmarkPoint {
+    pub fn rate_per_thread(&self) -> f64 {
+        self.rate / self.threads as f64
+    }
+}
+
+/// How fast this machine tries candidates, and how that scales with the
+/// number of threads.
+///
+/// Each try is what the [`Cracker`] does for a candidate: the 4096 rounds of
+/// PBKDF2-HMAC-SHA1 for the PMK, then the HMAC for the PMKID.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Benchmark {
+    /// When it was run, in seconds since the Unix epoch.
+    pub date: u64,
+    /// The [`Pbkdf2Backend::name`] it was run with, empty for benchmarks
+    /// from before there was a choice.
+    #[serde(default)]
+    pub backend: String,
+    /// From one thread up to every core, doubling each time.
+    pub points: Vec<BenchmarkPoint>,
+}
+
+impl Benchmark {
+    /// Measures the throughput of `backend` with 1, 2, 4… threads, up to as
+    /// many as a [`Cracker`] runs on, spending `step` on each. Blocks until
+    /// done.
+    ///
+    /// # Panics
+    ///
+    /// If the CPU doesn't [support](Pbkdf2Backend::is_supported) `backend`.
+    pub fn run(step: Duration, backend: Pbkdf2Backend) -> Result<Self, ThreadPoolBuildError> {
+        let max_threads = rayon::current_num_threads();
+        let mut thread_counts: Vec<usize> = (0..)
+            .map(|power| 1 << power)
+            .take_while(|&threads| threads < max_threads)
+            .collect();
+        thread_counts.push(max_threads);
+
+        let mut points = Vec::new();
+        for threads in thread_counts {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()?;
+            let tries = pool.install(|| measure(threads, step, backend));
+            points.push(BenchmarkPoint {
+                threads,
+                rate: tries as f64 / step.as_secs_f64(),
+            });
+        }
+
+        Ok(Self {
+            date: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+            backend: backend.name().to_owned(),
+            points,
+        })
+    }
+
+    /// Reads the benchmark saved at `path`, if there is one.
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        Ok(Some(serde_json::from_reader(io::BufReader::new(file))?))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        save_json(path, self)
+    }
+
+    /// The best throughput measured, in PMKs per second.
+    pub fn rate(&self) -> f64 {
+        self.points
+            .iter()
+            .map(|point| point.rate)
+            .fold(0.0, f64::max)
+    }
+
+    /// Roughly how long trying `candidates` candidates takes at the best
+    /// throughput measured.
+    pub fn estimate(&self, candidates: u64) -> Option<Duration> {
+        let rate = self.rate();
+        (rate > 0.0).then(|| Duration::from_secs_f64(candidates as f64 / rate))
+    }
+}
+
+/// Tries [`CANDIDATE`] on `threads` threads of the current pool for `step`,
+/// as many at once as `backend` takes, returning how many tries were made.
+fn measure(threads: usize, step: Duration, backend: Pbkdf2Backend) -> u64 {
+    let target = PmkidTarget {
+        essid: b"benchmark".to_vec(),
+        mac_ap: [0; 6],
+        mac_sta: [0; 6],
+        pmkid: [0; 16],
+    };
+    let mut cracker = Cracker::new(target);
+    cracker.set_backend(backend);
+    let group = vec![CANDIDATE.to_vec(); backend.lanes()];
+    // Never filled in, as nothing matches the all-zero PMKID
+    let found = Found::new(vec![None]);
+
+    let deadline = Instant::now() + step;
+    (0..threads)
+        .into_par_iter()
+        .with_max_len(1)
+        .map(|_| {
+            let mut tries = 0;
+            while Instant::now() < deadline {
+                cracker.find_matches(&group, &found);
+                tries += group.len() as u64;
+            }
+            tries
+        })
+        .sum()
+}
@@ -0,0 +1,228 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use serde::{Deserialize, Deserializer, Serialize};
+use sha1_smol::Sha1;
+
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::{save_json, CandidateFilter, HybridSide, MaskSpec, PmkidTarget, Position};
+
+/// How much of each end of a file goes into its identity hash.
+const IDENTITY_SAMPLE: u64 = 1024 * 1024;
+
+/// Identifies a wordlist or rule file well enough to tell whether it changed
+/// since a checkpoint was written.
+///
+/// Only the first and last MiB are hashed, so that checking a multi-GB list
+/// doesn't take longer than the resume it is guarding.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileIdentity {
+    pub path: PathBuf,
+    pub size: u64,
+    pub hash: String,
+}
+
+impl FileIdentity {
+    pub fn of(path: &Path) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let size = file.metadata()?.len();
+
+        let mut hasher = Sha1::new();
+        let mut sample = Vec::new();
+        file.by_ref()
+            .take(IDENTITY_SAMPLE)
+            .read_to_end(&mut sample)?;
+        hasher.update(&sample);
+
+        if size > IDENTITY_SAMPLE {
+            sample.clear();
+            file.seek(SeekFrom::Start(
+                size.saturating_sub(IDENTITY_SAMPLE).max(IDENTITY_SAMPLE),
+            ))?;
+            file.read_to_end(&mut sample)?;
+            hasher.update(&sample);
+        }
+
+        Ok(Self {
+            path: path.to_owned(),
+            size,
+            hash: hasher.digest().to_string(),
+        })
+    }
+}
+
+/// The attack a checkpoint belongs to, and its settings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum Attack {
+    /// The wordlists of a [`WordlistQueue`](super::WordlistQueue), in order,
+    /// and the rule file they were [`Mangled`](super::Mangled) with.
+    Wordlist {
+        wordlists: Vec<FileIdentity>,
+        #[serde(default)]
+        rules: Option<FileIdentity>,
+    },
+    /// A mask attack, which has no files to go stale.
+    Mask { mask: MaskSpec },
+    /// Wordlists whose lines are each combined with a mask by a
+    /// [`Hybrid`](super::Hybrid), one mask length after the other when
+    /// incrementing.
+    Hybrid {
+        wordlists: Vec<FileIdentity>,
+        mask: MaskSpec,
+        side: HybridSide,
+    },
+    /// The two wordlists a [`Combinator`](super::Combinator) joins, and the
+    /// rule files each side was [`Mangled`](super::Mangled) with.
+    Combinator {
+        left: FileIdentity,
+        right: FileIdentity,
+        #[serde(default)]
+        separator: String,
+        #[serde(default)]
+        left_rules: Option<FileIdentity>,
+        #[serde(default)]
+        right_rules: Option<FileIdentity>,
+    },
+}
+
+/// Everything needed to pick a run back up where it stopped.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Checkpoints from before several targets could be cracked at once
+    /// have a single `target`.
+    #[serde(alias = "target", deserialize_with = "one_or_many")]
+    pub targets: Vec<PmkidTarget>,
+    pub attack: Attack,
+    /// What candidates were skipped, so that resuming skips the same ones.
+    #[serde(default)]
+    pub filter: CandidateFilter,
+    /// Every candidate before this position in the source has been tried.
+    #[serde(flatten)]
+    pub position: Position,
+}
+
+impl Checkpoint {
+    pub fn new(targets: Vec<PmkidTarget>, attack: Attack) -> Self {
+        Self {
+            targets,
+            attack,
+            filter: CandidateFilter::default(),
+            position: Position::default(),
+        }
+    }
+
+    /// Reads the checkpoint at `path`, if there is one.
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        Ok(Some(serde_json::from_reader(io::BufReader::new(file))?))
+    }
+
+    /// Checks that the files are still the ones the checkpoint was written
+    /// for.
+    pub fn verify(&self) -> io::Result<bool> {
+        let files: Vec<&FileIdentity> = match &self.attack {
+            Attack::Wordlist { wordlists, rules } => wordlists.iter().chain(rules).collect(),
+            Attack::Mask { .. } => Vec::new(),
+            Attack::Hybrid { wordlists, .. } => wordlists.iter().collect(),
+            Attack::Combinator {
+                left,
+                right,
+                left_rules,
+                right_rules,
+                ..
+            } => [left, right]
+                .into_iter()
+                .chain(left_rules)
+                .chain(right_rules)
+                .collect(),
+        };
+        for file in files {
+            if FileIdentity::of(&file.path)? != *file {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn save(&self, path: &Path) -> io::Result<()> {
+        save_json(path, self)
+    }
+}
+
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<PmkidTarget>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(PmkidTarget),
+        Many(Vec<PmkidTarget>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(target) => vec![target],
+        OneOrMany::Many(targets) => targets,
+    })
+}
+
+/// Periodically persists a [`Checkpoint`] for a running
+/// [`Cracker`](super::Cracker).
+///
+/// The cracker advances the position as batches complete. The UI can also
+/// [`save`](Self::save) on demand, e.g. when pausing or closing.
+#[derive(Debug)]
+pub struct Checkpointer {
+    path: PathBuf,
+    interval: Duration,
+    state: Mutex<(Checkpoint, Instant)>,
+}
+
+impl Checkpointer {
+    pub fn new(path: PathBuf, checkpoint: Checkpoint, interval: Duration) -> Self {
+        Self {
+            path,
+            interval,
+            state: Mutex::new((checkpoint, Instant::now())),
+        }
+    }
+
+    pub fn position(&self) -> Position {
+        self.state.lock().unwrap().0.position
+    }
+
+    /// Records that every candidate before `position` has been tried,
+    /// writing the checkpoint out if the interval has passed.
+    pub(crate) fn advance(&self, position: Position) {
+        let mut state = self.state.lock().unwrap();
+        state.0.position = position;
+        if state.1.elapsed() >= self.interval {
+            state.1 = Instant::now();
+            if let Err(err) = state.0.save(&self.path) {
+                log::warn!("Failed to write checkpoint, {}", err);
+            }
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.1 = Instant::now();
+        state.0.save(&self.path)
+    }
+
+    /// Deletes the checkpoint file, once the run it belongs to is over.
+    pub fn remove(&self) -> io::Result<()> {
+        let _state = self.state.lock().unwrap();
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::io;
+
+use super::pairs::Pairs;
+use super::{Batch, CandidateSource, Position};
+
+/// Joins every word of a left source with every word of a right list, like
+/// hashcat's `-a 1`, e.g. `blue` and `house` into `bluehouse`.
+///
+/// The left source is streamed, while the right list is held in memory. The
+/// pairings themselves are only made a batch at a time. Progress and
+/// positions count the pairings, out of the number of left words times the
+/// number of right words, so the left words have to be counted up front,
+/// e.g. with [`count_candidates`].
+#[derive(Debug)]
+pub struct Combinator<S> {
+    pairs: Pairs<S>,
+    right: Vec<Vec<u8>>,
+    separator: Vec<u8>,
+}
+
+impl<S: CandidateSource> Combinator<S> {
+    /// Joins the `left_words` words of `left` with each of `right`, putting
+    /// `separator` in between.
+    pub fn new(left: S, left_words: u64, right: Vec<Vec<u8>>, separator: Vec<u8>) -> Self {
+        Self {
+            pairs: Pairs::new(left, right.len() as u64, left_words),
+            right,
+            separator,
+        }
+    }
+}
+
+impl<S: CandidateSource> CandidateSource for Combinator<S> {
+    fn total(&self) -> u64 {
+        self.pairs.total()
+    }
+
+    fn progress(&self) -> u64 {
+        self.pairs.progress()
+    }
+
+    fn next_batch(&mut self) -> io::Result<Option<Batch>> {
+        let (right, separator) = (&self.right, &self.separator);
+        self.pairs
+            .next_batch(|left, index| Some([left, separator, &right[index as usize]].concat()))
+    }
+
+    fn skip_to(&mut self, position: Position) -> io::Result<()> {
+        self.pairs.skip_to(position)
+    }
+}
+
+/// Reads all of `source` into memory, e.g. for the right side of a
+/// [`Combinator`].
+pub fn collect_candidates<S: CandidateSource>(mut source: S) -> io::Result<Vec<Vec<u8>>> {
+    let mut candidates = Vec::new();
+    while let Some(batch) = source.next_batch()? {
+        candidates.extend(batch.candidates);
+    }
+    Ok(candidates)
+}
+
+/// Reads through `source` and counts its candidates, e.g. for the left side
+/// of a [`Combinator`] when it goes through rules.
+pub fn count_candidates<S: CandidateSource>(mut source: S) -> io::Result<u64> {
+    let mut count = 0;
+    while let Some(batch) = source.next_batch()? {
+        count += batch.candidates.len() as u64;
+    }
+    Ok(count)
+}
@@ -0,0 +1,107 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// Shared flag used to ask a running [`Cracker`](super::Cracker) to stop.
+///
+/// Clones refer to the same flag, so one can be handed to the UI while the
+/// cracker keeps another.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Shared marker used to make a running [`Cracker`](super::Cracker) move
+/// past sources of a [`WordlistQueue`](super::WordlistQueue).
+///
+/// Like [`CancelToken`], clones refer to the same marker.
+#[derive(Debug, Clone, Default)]
+pub struct SkipToken(Arc<AtomicUsize>);
+
+impl SkipToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gives up on what is left of the `source`th source, and any before it.
+    pub fn skip(&self, source: usize) {
+        self.0.fetch_max(source + 1, Ordering::Relaxed);
+    }
+
+    pub fn is_skipped(&self, source: usize) -> bool {
+        source < self.first_unskipped()
+    }
+
+    /// Index of the first source that hasn't been skipped.
+    pub(crate) fn first_unskipped(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Default)]
+struct PauseState {
+    paused: AtomicBool,
+    lock: Mutex<()>,
+    resumed: Condvar,
+}
+
+/// Shared switch used to park the workers of a running
+/// [`Cracker`](super::Cracker) and let them carry on later.
+///
+/// Like [`CancelToken`], clones refer to the same switch.
+#[derive(Debug, Clone, Default)]
+pub struct PauseToken(Arc<PauseState>);
+
+impl PauseToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pause(&self) {
+        self.0.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        let _guard = self.0.lock.lock().unwrap();
+        self.0.paused.store(false, Ordering::Relaxed);
+        self.0.resumed.notify_all();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.0.paused.load(Ordering::Relaxed)
+    }
+
+    /// Blocks the calling worker for as long as the switch is paused, or
+    /// until `cancel` is set.
+    pub(crate) fn wait(&self, cancel: &CancelToken) {
+        if !self.is_paused() {
+            return;
+        }
+
+        let mut guard = self.0.lock.lock().unwrap();
+        while self.is_paused() && !cancel.is_cancelled() {
+            // Cancelling doesn't notify us, so check back every now and then.
+            guard = self
+                .0
+                .resumed
+                .wait_timeout(guard, Duration::from_millis(100))
+                .unwrap()
+                .0;
+        }
+    }
+}
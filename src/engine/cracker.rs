@@ -0,0 +1,593 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use rayon::prelude::*;
+use subtle::ConstantTimeEq;
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::filter::FilterState;
+use super::pbkdf2::ITERATIONS;
+use super::{
+    CancelToken, CandidateFilter, CandidateSource, Checkpointer, DefaultPbkdf2, PauseToken,
+    Pbkdf2Backend, Pbkdf2Sha1, PmkidTarget, Position, Potfile, SkipStats, SkipToken, StatusHandle,
+    MAX_LANES, RAW_PSK_LEN,
+};
+
+/// How many batches the reader may get ahead of the workers.
+const PIPELINE_DEPTH: usize = 2;
+
+/// How many candidates a worker tries in a row before adding them to the
+/// [`StatusHandle`]. Small enough for the status to move smoothly even with
+/// PBKDF2 in the way, large enough for the shared counters not to matter
+/// when it isn't.
+const CHUNK_LEN: usize = 64;
+
+/// How a [`Cracker::run`] ended. `tried` is how many candidates were tried
+/// by then, not counting those skipped before a resume.
+///
+/// Passphrases are in the order of [`Cracker::targets`]. See
+/// [`format_passphrase`](super::format_passphrase) for showing them. One
+/// that is [`RAW_PSK_LEN`] long is a raw PSK.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Every target was found, the last one `elapsed` after the start.
+    Found {
+        passphrases: Vec<Vec<u8>>,
+        elapsed: Duration,
+        tried: u64,
+        skipped: SkipStats,
+        /// Every passphrase was already in the [`Potfile`], so nothing was
+        /// tried.
+        from_potfile: bool,
+    },
+    /// Every candidate was tried without finding every target. `found`
+    /// has the passphrases of those that were.
+    Exhausted {
+        found: Vec<Option<Vec<u8>>>,
+        elapsed: Duration,
+        tried: u64,
+        skipped: SkipStats,
+    },
+    /// The run was stopped through its [`CancelToken`].
+    Cancelled {
+        found: Vec<Option<Vec<u8>>>,
+        elapsed: Duration,
+        tried: u64,
+    },
+}
+
+impl Outcome {
+    /// The passphrase found for the target at `index` of
+    /// [`Cracker::targets`], if it was.
+    pub fn passphrase(&self, index: usize) -> Option<&[u8]> {
+        match self {
+            Self::Found { passphrases, .. } => passphrases.get(index).map(Vec::as_slice),
+            Self::Exhausted { found, .. } | Self::Cancelled { found, .. } => {
+                found.get(index)?.as_deref()
+            }
+        }
+    }
+}
+
+/// Runs candidates against one or more [`PmkidTarget`]s.
+#[derive(Debug, Clone)]
+pub struct Cracker {
+    targets: Vec<PmkidTarget>,
+    /// The targets by ESSID, as their PMKs only depend on that.
+    groups: Vec<EssidGroup>,
+    cancel: CancelToken,
+    pause: PauseToken,
+    skip: SkipToken,
+    status: StatusHandle,
+    backend: Pbkdf2Backend,
+    filter: CandidateFilter,
+    checkpointer: Option<Arc<Checkpointer>>,
+    potfile: Option<Arc<Potfile>>,
+}
+
+/// The targets sharing an ESSID, so that the PMK of a candidate is derived
+/// once for all of them.
+#[derive(Debug, Clone)]
+struct EssidGroup {
+    essid: Vec<u8>,
+    /// The index of each target in [`Cracker::targets`], and its
+    /// [`PmkidTarget::message`], worked out once rather than per candidate.
+    members: Vec<(usize, [u8; 20])>,
+}
+
+/// The passphrases found so far in a run, shared by its workers.
+#[derive(Debug)]
+pub(crate) struct Found {
+    passphrases: Mutex<Vec<Option<Vec<u8>>>>,
+    /// Whether each target is in `passphrases`, so that workers can leave
+    /// it out without locking.
+    solved: Vec<AtomicBool>,
+    remaining: AtomicUsize,
+}
+
+impl Found {
+    pub(crate) fn new(passphrases: Vec<Option<Vec<u8>>>) -> Self {
+        Self {
+            solved: passphrases
+                .iter()
+                .map(|passphrase| AtomicBool::new(passphrase.is_some()))
+                .collect(),
+            remaining: AtomicUsize::new(passphrases.iter().filter(|p| p.is_none()).count()),
+            passphrases: Mutex::new(passphrases),
+        }
+    }
+
+    fn is_solved(&self, target: usize) -> bool {
+        self.solved[target].load(Ordering::Relaxed)
+    }
+
+    fn is_complete(&self) -> bool {
+        self.remaining.load(Ordering::Relaxed) == 0
+    }
+
+    /// Records `passphrase` for the target at `target`, returning whether
+    /// it wasn't found already.
+    fn insert(&self, target: usize, passphrase: &[u8]) -> bool {
+        let mut passphrases = self.passphrases.lock().unwrap();
+        if passphrases[target].is_some() {
+            return false;
+        }
+        passphrases[target] = Some(passphrase.to_vec());
+        self.solved[target].store(true, Ordering::Relaxed);
+        self.remaining.fetch_sub(1, Ordering::Relaxed);
+        true
+    }
+
+    fn into_inner(self) -> Vec<Option<Vec<u8>>> {
+        self.passphrases.into_inner().unwrap()
+    }
+}
+
+/// [`Cracker::with_targets`] was given no targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoTargets;
+
+impl fmt::Display for NoTargets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "There are no targets to crack")
+    }
+}
+
+impl Error for NoTargets {}
+
+/// Returned from the worker closure to make rayon stop handing out candidates.
+enum Stop {
+    /// The last target was found, this long after the start.
+    Done(Duration),
+    Skipped,
+    Cancelled,
+}
+
+impl Cracker {
+    pub fn new(target: PmkidTarget) -> Self {
+        Self::build(vec![target])
+    }
+
+    /// A cracker for all of `targets` at once. The PMK of each candidate is
+    /// derived once per ESSID, however many of the targets share it.
+    pub fn with_targets(targets: Vec<PmkidTarget>) -> Result<Self, NoTargets> {
+        if targets.is_empty() {
+            return Err(NoTargets);
+        }
+        Ok(Self::build(targets))
+    }
+
+    fn build(targets: Vec<PmkidTarget>) -> Self {
+        let mut groups: Vec<EssidGroup> = Vec::new();
+        for (index, target) in targets.iter().enumerate() {
+            let member = (index, target.message());
+            match groups.iter_mut().find(|group| group.essid == target.essid) {
+                Some(group) => group.members.push(member),
+                None => groups.push(EssidGroup {
+                    essid: target.essid.clone(),
+                    members: vec![member],
+                }),
+            }
+        }
+
+        Self {
+            targets,
+            groups,
+            cancel: CancelToken::new(),
+            pause: PauseToken::new(),
+            skip: SkipToken::new(),
+            status: StatusHandle::new(),
+            backend: Pbkdf2Backend::detect(),
+            filter: CandidateFilter::default(),
+            checkpointer: None,
+            potfile: None,
+        }
+    }
+
+    pub fn targets(&self) -> &[PmkidTarget] {
+        &self.targets
+    }
+
+    /// A token that stops [`run`](Self::run) once cancelled, even from
+    /// another thread.
+    pub fn cancel_token(&self) -> CancelToken {
+        self.cancel.clone()
+    }
+
+    /// A token that parks the workers of [`run`](Self::run) while paused.
+    pub fn pause_token(&self) -> PauseToken {
+        self.pause.clone()
+    }
+
+    /// A token that makes [`run`](Self::run) drop the rest of a source and
+    /// carry on with the next one.
+    pub fn skip_token(&self) -> SkipToken {
+        self.skip.clone()
+    }
+
+    /// A handle to poll the [`Status`](super::Status) of [`run`](Self::run)
+    /// through, every [`STATUS_INTERVAL`](super::STATUS_INTERVAL) or so.
+    pub fn status_handle(&self) -> StatusHandle {
+        self.status.clone()
+    }
+
+    pub fn backend(&self) -> Pbkdf2Backend {
+        self.backend
+    }
+
+    /// Derives PMKs with `backend` rather than the fastest one this CPU
+    /// supports.
+    ///
+    /// # Panics
+    ///
+    /// If the CPU doesn't [support](Pbkdf2Backend::is_supported) `backend`.
+    pub fn set_backend(&mut self, backend: Pbkdf2Backend) {
+        assert!(
+            backend.is_supported(),
+            "{} isn't supported by this CPU",
+            backend
+        );
+        self.backend = backend;
+    }
+
+    /// Decides which candidates get tried. Candidates that can't be WPA
+    /// passphrases are dropped either way.
+    pub fn set_filter(&mut self, filter: CandidateFilter) {
+        self.filter = filter;
+    }
+
+    /// Keeps `checkpointer` up to date while running, resuming from its
+    /// position. The checkpoint is removed once the run finds every
+    /// passphrase or exhausts the source, but kept when it is cancelled.
+    pub fn set_checkpointer(&mut self, checkpointer: Arc<Checkpointer>) {
+        self.checkpointer = Some(checkpointer);
+    }
+
+    /// Looks the targets up in `potfile` before trying any candidates, and
+    /// records their passphrases there as they are found.
+    pub fn set_potfile(&mut self, potfile: Arc<Potfile>) {
+        self.potfile = Some(potfile);
+    }
+
+    /// The outcome of a run if every target is already in the potfile,
+    /// without having to open a source first.
+    pub fn recall(&self) -> Option<Outcome> {
+        let passphrases = self.recall_each().into_iter().collect::<Option<_>>()?;
+
+        self.remove_checkpoint();
+        Some(Outcome::Found {
+            passphrases,
+            elapsed: Duration::ZERO,
+            tried: 0,
+            skipped: SkipStats::default(),
+            from_potfile: true,
+        })
+    }
+
+    /// The passphrase of each target that is in the potfile.
+    ///
+    /// Passphrases are checked against their target, in case the potfile
+    /// was edited by hand.
+    fn recall_each(&self) -> Vec<Option<Vec<u8>>> {
+        let potfile = match &self.potfile {
+            Some(potfile) => potfile,
+            None => return vec![None; self.targets.len()],
+        };
+        self.targets
+            .iter()
+            .map(|target| {
+                let passphrase = potfile.get(target)?;
+                if !is_match(target, &passphrase) {
+                    log::warn!("Wrong passphrase in {}", potfile.path().display());
+                    return None;
+                }
+                Some(passphrase)
+            })
+            .collect()
+    }
+
+    /// Tries every candidate of `group` against the targets not in `found`
+    /// yet, recording those that match. Their PMKs are derived together,
+    /// once per ESSID, so `group` should be no longer than
+    /// [`Pbkdf2Backend::lanes`].
+    pub(crate) fn find_matches(&self, group: &[Vec<u8>], found: &Found) {
+        // Raw PSKs are PMKs already, whatever the ESSID
+        let mut raw_pmks = [[0; 32]; MAX_LANES];
+        let mut raw_lanes = [0; MAX_LANES];
+        let mut raw_count = 0;
+        let mut passphrases: [&[u8]; MAX_LANES] = [&[]; MAX_LANES];
+        let mut lanes = [0; MAX_LANES];
+        let mut count = 0;
+        for (lane, candidate) in group.iter().enumerate() {
+            if candidate.len() == RAW_PSK_LEN {
+                // Only raw PSKs make it past the filter at this length
+                if hex::decode_to_slice(candidate, &mut raw_pmks[raw_count]).is_ok() {
+                    raw_lanes[raw_count] = lane;
+                    raw_count += 1;
+                }
+            } else {
+                passphrases[count] = candidate;
+                lanes[count] = lane;
+                count += 1;
+            }
+        }
+
+        let mut pmks = [[0; 32]; MAX_LANES];
+        for essid_group in &self.groups {
+            // Solved targets leave the active set, and with them any ESSID
+            // that has nothing left to find
+            if essid_group
+                .members
+                .iter()
+                .all(|&(target, _)| found.is_solved(target))
+            {
+                continue;
+            }
+
+            self.backend
+                .derive_pmks(&essid_group.essid, &passphrases[..count], &mut pmks);
+            let derived = pmks[..count].iter().zip(&lanes[..count]);
+            let raw = raw_pmks[..raw_count].iter().zip(&raw_lanes[..raw_count]);
+            for (pmk, &lane) in derived.chain(raw) {
+                for (target, message) in &essid_group.members {
+                    if !found.is_solved(*target)
+                        && pmk_matches(pmk, message, &self.targets[*target].pmkid)
+                    {
+                        self.record(*target, &group[lane], found);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Adds a passphrase a worker found to `found`, the status and the
+    /// potfile.
+    fn record(&self, target: usize, passphrase: &[u8], found: &Found) {
+        if !found.insert(target, passphrase) {
+            return;
+        }
+        self.status.set_found(target, passphrase);
+        if let Some(potfile) = &self.potfile {
+            if let Err(err) = potfile.insert(&self.targets[target], passphrase) {
+                log::warn!("Failed to write potfile, {}", err);
+            }
+        }
+    }
+
+    /// Tries candidates from `source` until every target is found, the
+    /// source is exhausted or the run is cancelled. Targets already in the
+    /// potfile are left out, and if that is all of them, the source isn't
+    /// read at all.
+    ///
+    /// Blocks until then. The source is read and filtered on its own
+    /// thread, at most a couple of batches ahead of the workers. Once the
+    /// last target is found, the other workers stop picking up new
+    /// candidates. Errors reading the source end the run.
+    pub fn run<S: CandidateSource>(&self, mut source: S) -> io::Result<Outcome> {
+        if let Some(outcome) = self.recall() {
+            return Ok(outcome);
+        }
+        let found = Found::new(self.recall_each());
+
+        let total = source.total();
+        source.skip_to(
+            self.checkpointer
+                .as_ref()
+                .map_or_else(Position::default, |c| c.position()),
+        )?;
+        let mut progress = source.progress();
+        self.status
+            .start(progress, total, found.passphrases.lock().unwrap().clone());
+
+        let total_crack_time = Instant::now();
+        let (sender, receiver) = mpsc::sync_channel(PIPELINE_DEPTH);
+
+        let stopped: io::Result<_> = thread::scope(|scope| {
+            scope.spawn(move || {
+                let mut filter = FilterState::new(self.filter);
+                let mut skipped = 0;
+                loop {
+                    let first_unskipped = self.skip.first_unskipped();
+                    let next = if first_unskipped > skipped {
+                        skipped = first_unskipped;
+                        source.skip_to(Position::start_of(first_unskipped))
+                    } else {
+                        Ok(())
+                    }
+                    .and_then(|()| source.next_batch());
+
+                    let failed = next.is_err();
+                    let batch = match next.transpose() {
+                        Some(batch) => batch.map(|mut batch| {
+                            let stats = filter.apply(&mut batch.candidates);
+                            (batch, stats)
+                        }),
+                        None => break,
+                    };
+                    // The receiver is gone once the workers are done with us
+                    if sender.send(batch).is_err() || failed {
+                        break;
+                    }
+                }
+            });
+
+            let mut current_source = None;
+            let mut skip_stats = SkipStats::default();
+            let mut tried_total = 0;
+            for batch in receiver {
+                let (batch, stats) = batch?;
+                skip_stats += stats;
+                if current_source != Some(batch.end.source) {
+                    current_source = Some(batch.end.source);
+                    self.status.set_source(batch.end.source);
+                }
+
+                // Spread the progress of the batch over its candidates, so
+                // it moves smoothly and lands exactly on `batch.progress`
+                let start = progress;
+                let span = batch.progress.saturating_sub(start);
+                let count = batch.candidates.len() as u64;
+                let tried = AtomicU64::new(0);
+
+                let stop = batch
+                    .candidates
+                    .par_chunks(CHUNK_LEN)
+                    .find_map_any(|chunk| {
+                        let mut stop = None;
+                        let mut chunk_tried = 0;
+                        for group in chunk.chunks(self.backend.lanes()) {
+                            self.pause.wait(&self.cancel);
+                            if self.cancel.is_cancelled() {
+                                stop = Some(Stop::Cancelled);
+                                break;
+                            }
+                            if self.skip.is_skipped(batch.end.source) {
+                                stop = Some(Stop::Skipped);
+                                break;
+                            }
+                            // No point finishing the chunk once another worker
+                            // has found the last passphrase
+                            if found.is_complete() {
+                                break;
+                            }
+
+                            chunk_tried += group.len();
+                            self.find_matches(group, &found);
+                            if found.is_complete() {
+                                stop = Some(Stop::Done(total_crack_time.elapsed()));
+                                break;
+                            }
+                        }
+
+                        if chunk_tried > 0 {
+                            let tried = tried.fetch_add(chunk_tried as u64, Ordering::Relaxed)
+                                + chunk_tried as u64;
+                            self.status.add(
+                                chunk_tried as u64,
+                                start + span * tried / count,
+                                &chunk[chunk_tried - 1],
+                            );
+                        }
+                        stop
+                    });
+                tried_total += tried.into_inner();
+
+                match stop {
+                    Some(Stop::Done(elapsed)) => {
+                        self.remove_checkpoint();
+                        return Ok((Some(elapsed), tried_total, skip_stats));
+                    }
+                    Some(Stop::Skipped) => {
+                        progress = batch.progress;
+                        self.status.advance(progress);
+                        // Resuming shouldn't bring a skipped source back
+                        if let Some(checkpointer) = &self.checkpointer {
+                            checkpointer.advance(Position::start_of(batch.end.source + 1));
+                        }
+                    }
+                    Some(Stop::Cancelled) => return Ok((None, tried_total, skip_stats)),
+                    None => {
+                        progress = batch.progress;
+                        self.status.advance(progress);
+                        if let Some(checkpointer) = &self.checkpointer {
+                            checkpointer.advance(batch.end);
+                        }
+                    }
+                }
+            }
+
+            self.remove_checkpoint();
+            Ok((None, tried_total, skip_stats))
+        });
+
+        let (done, tried, skipped) = stopped?;
+        let found = found.into_inner();
+        Ok(match done {
+            Some(elapsed) => Outcome::Found {
+                passphrases: found.into_iter().flatten().collect(),
+                elapsed,
+                tried,
+                skipped,
+                from_potfile: false,
+            },
+            None if self.cancel.is_cancelled() => Outcome::Cancelled {
+                found,
+                elapsed: total_crack_time.elapsed(),
+                tried,
+            },
+            None => Outcome::Exhausted {
+                found,
+                elapsed: total_crack_time.elapsed(),
+                tried,
+                skipped,
+            },
+        })
+    }
+
+    fn remove_checkpoint(&self) {
+        if let Some(checkpointer) = &self.checkpointer {
+            if let Err(err) = checkpointer.remove() {
+                log::warn!("Failed to remove checkpoint, {}", err);
+            }
+        }
+    }
+}
+
+/// Whether `passphrase` produces the PMKID of `target`.
+///
+/// PMKID = HMAC-SHA1-128(PMK, "PMK Name" | MAC_AP | MAC_STA), where the
+/// PMK is the PBKDF2 of the passphrase, derived on its own. Candidates
+/// being run go through [`Cracker::find_matches`] instead, a whole group at
+/// a time.
+fn is_match(target: &PmkidTarget, passphrase: &[u8]) -> bool {
+    /*
+        derive the pbkdf2 using the network name and passphrase
+        this is usually the most time consuming part
+    */
+    let mut key_out = [0u8; 32];
+    if passphrase.len() == RAW_PSK_LEN {
+        // Only raw PSKs make it past the filter at this length
+        if hex::decode_to_slice(passphrase, &mut key_out).is_err() {
+            return false;
+        }
+    } else {
+        DefaultPbkdf2::pbkdf2(passphrase, &target.essid, ITERATIONS as u32, &mut key_out);
+    }
+    pmk_matches(&key_out, &target.message(), &target.pmkid)
+}
+
+fn pmk_matches(pmk: &[u8; 32], message: &[u8; 20], pmkid: &[u8; 16]) -> bool {
+    /*
+        get the hmac-sha1 of the message using the pmk as key
+        and compare its first 128 bits with the pmkid
+    */
+    let mac = DefaultPbkdf2::hmac(pmk, message);
+    mac[..16].ct_eq(pmkid).into()
+}
@@ -0,0 +1,129 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::AddAssign;
+
+/// WPA-PSK passphrases are 8 to 63 characters long.
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+pub const MAX_PASSPHRASE_LEN: usize = 63;
+
+/// A raw PSK is the 32-byte PMK itself, written out in hex.
+pub const RAW_PSK_LEN: usize = 64;
+
+/// Decides which candidates are worth deriving a PMK for.
+///
+/// Candidates that can't be WPA passphrases are always dropped: those of the
+/// wrong length, and those with control characters, which can't be typed in.
+/// IEEE 802.11 only allows printable ASCII, but bytes above it are kept, as
+/// devices do take UTF-8 passphrases, and wordlists carry them on purpose.
+/// The rest of the filtering is opt-in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CandidateFilter {
+    /// Drop candidates already tried during the run. Every candidate is kept
+    /// in memory to do so, and the memory is lost when resuming.
+    pub dedupe: bool,
+    /// Take 64 hex digit candidates as raw PSKs, i.e. as the PMK itself,
+    /// instead of dropping them for being too long.
+    pub raw_psk: bool,
+}
+
+impl CandidateFilter {
+    /// Whether `candidate` is a raw PSK, as far as this filter is concerned.
+    pub fn is_raw_psk(&self, candidate: &[u8]) -> bool {
+        self.raw_psk
+            && candidate.len() == RAW_PSK_LEN
+            && candidate.iter().all(u8::is_ascii_hexdigit)
+    }
+}
+
+/// How many candidates a [`CandidateFilter`] dropped, and why.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SkipStats {
+    pub too_short: u64,
+    pub too_long: u64,
+    /// Candidates with control characters, ASCII 0-31 or 127.
+    pub unprintable: u64,
+    pub duplicate: u64,
+}
+
+impl SkipStats {
+    pub fn total(&self) -> u64 {
+        self.too_short + self.too_long + self.unprintable + self.duplicate
+    }
+}
+
+impl AddAssign for SkipStats {
+    fn add_assign(&mut self, other: Self) {
+        self.too_short += other.too_short;
+        self.too_long += other.too_long;
+        self.unprintable += other.unprintable;
+        self.duplicate += other.duplicate;
+    }
+}
+
+impl fmt::Display for SkipStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total();
+        write!(
+            f,
+            "{} {} skipped ({} too short, {} too long, {} unprintable, {} duplicate)",
+            total,
+            if total == 1 {
+                "candidate"
+            } else {
+                "candidates"
+            },
+            self.too_short,
+            self.too_long,
+            self.unprintable,
+            self.duplicate
+        )
+    }
+}
+
+/// A [`CandidateFilter`] applied over a whole run, remembering what it has
+/// seen.
+#[derive(Debug)]
+pub(crate) struct FilterState {
+    filter: CandidateFilter,
+    seen: HashSet<Vec<u8>>,
+}
+
+impl FilterState {
+    pub fn new(filter: CandidateFilter) -> Self {
+        Self {
+            filter,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Drops the candidates that don't pass the filter, keeping the order of
+    /// the rest.
+    pub fn apply(&mut self, candidates: &mut Vec<Vec<u8>>) -> SkipStats {
+        let mut stats = SkipStats::default();
+        candidates.retain(|candidate| {
+            if candidate.len() < MIN_PASSPHRASE_LEN {
+                stats.too_short += 1;
+                return false;
+            }
+            if candidate.len() > MAX_PASSPHRASE_LEN && !self.filter.is_raw_psk(candidate) {
+                stats.too_long += 1;
+                return false;
+            }
+            if candidate.iter().any(u8::is_ascii_control) {
+                stats.unprintable += 1;
+                return false;
+            }
+            if self.filter.dedupe && !self.seen.insert(candidate.clone()) {
+                stats.duplicate += 1;
+                return false;
+            }
+            true
+        });
+        stats
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use serde::{Deserialize, Serialize};
+
+use std::fs::File;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::{save_json, PmkidTarget};
+
+/// How a past run ended.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum HistoryOutcome {
+    /// The passphrase, as shown by [`format_passphrase`](super::format_passphrase).
+    Found {
+        passphrase: String,
+    },
+    Exhausted,
+    Cancelled,
+    /// The run stopped on an error, e.g. a wordlist that couldn't be read.
+    Failed {
+        error: String,
+    },
+}
+
+/// A single run, as recorded in the [`History`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Unique within the history, for removing entries.
+    pub id: u64,
+    pub target: PmkidTarget,
+    /// When the run started, in seconds since the Unix epoch.
+    pub started: u64,
+    pub duration: Duration,
+    /// How many candidates were tried.
+    pub tried: u64,
+    /// The kind of attack, e.g. `Wordlist` or `Mask ?d?d?d?d?d?d?d?d`.
+    pub attack: String,
+    /// The names of the wordlists or mask lengths, in order.
+    pub sources: Vec<String>,
+    /// The names of the rule files used, if any.
+    #[serde(default)]
+    pub rules: Vec<String>,
+    pub outcome: HistoryOutcome,
+}
+
+/// Every run so far, oldest first, kept in a JSON file.
+#[derive(Debug)]
+pub struct History {
+    path: PathBuf,
+    entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    /// Reads the history at `path`. A missing file is an empty history,
+    /// which is created once something is added to it.
+    pub fn load(path: PathBuf) -> io::Result<Self> {
+        let entries = match File::open(&path) {
+            Ok(file) => serde_json::from_reader(io::BufReader::new(file))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+        Ok(Self { path, entries })
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Adds `entry` and writes the history out. Its id is replaced with one
+    /// that isn't taken yet.
+    pub fn push(&mut self, mut entry: HistoryEntry) -> io::Result<()> {
+        entry.id = self
+            .entries
+            .iter()
+            .map(|entry| entry.id + 1)
+            .max()
+            .unwrap_or(0);
+        self.entries.push(entry);
+        self.save()
+    }
+
+    /// Removes the entry with the given id, if there is one, and writes the
+    /// history out.
+    pub fn remove(&mut self, id: u64) -> io::Result<()> {
+        self.entries.retain(|entry| entry.id != id);
+        self.save()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        save_json(&self.path, &self.entries)
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use serde::{Deserialize, Serialize};
+
+use std::io;
+
+use super::pairs::Pairs;
+use super::{Batch, CandidateSource, Mask, Position};
+
+/// Which end of each word the mask goes on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HybridSide {
+    /// `word` + mask, like hashcat's `-a 6`.
+    Append,
+    /// Mask + `word`, like hashcat's `-a 7`.
+    Prepend,
+}
+
+/// Combines every word of a source with every candidate of a [`Mask`].
+///
+/// The mask is run through in full for one word before moving on to the
+/// next. Progress and positions count the combined candidates, out of the
+/// number of words times the keyspace of the mask, so the number of words has
+/// to be known up front, e.g. from
+/// [`Wordlist::count_lines`](super::Wordlist::count_lines).
+#[derive(Debug)]
+pub struct Hybrid<S> {
+    pairs: Pairs<S>,
+    mask: Mask,
+    side: HybridSide,
+}
+
+impl<S: CandidateSource> Hybrid<S> {
+    pub fn new(source: S, mask: Mask, side: HybridSide, words: u64) -> Self {
+        Self {
+            pairs: Pairs::new(source, mask.keyspace(), words),
+            mask,
+            side,
+        }
+    }
+}
+
+impl<S: CandidateSource> CandidateSource for Hybrid<S> {
+    fn total(&self) -> u64 {
+        self.pairs.total()
+    }
+
+    fn progress(&self) -> u64 {
+        self.pairs.progress()
+    }
+
+    fn next_batch(&mut self) -> io::Result<Option<Batch>> {
+        let (mask, side) = (&self.mask, self.side);
+        self.pairs.next_batch(|word, index| {
+            let part = mask.candidate(index);
+            Some(match side {
+                HybridSide::Append => [word, &part].concat(),
+                HybridSide::Prepend => [&part, word].concat(),
+            })
+        })
+    }
+
+    fn skip_to(&mut self, position: Position) -> io::Result<()> {
+        self.pairs.skip_to(position)
+    }
+}
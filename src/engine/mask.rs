@@ -0,0 +1,328 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::ops::RangeInclusive;
+
+use super::{Batch, CandidateSource, Position, MIN_PASSPHRASE_LEN};
+
+/// The most candidates generated into one [`Batch`].
+const BATCH_SIZE: u64 = 4096;
+
+/// How many custom charsets a mask can refer to, as `?1` to `?4`.
+pub const CUSTOM_CHARSETS: usize = 4;
+
+const LOWER: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const UPPER: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &[u8] = b"0123456789";
+const SPECIAL: &[u8] = b" !\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+const HEX_LOWER: &[u8] = b"0123456789abcdef";
+const HEX_UPPER: &[u8] = b"0123456789ABCDEF";
+
+/// Why a mask or charset couldn't be used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MaskError {
+    Empty,
+    UnknownCharset(char),
+    /// A `?` at the very end, with no charset after it.
+    DanglingQuestionMark,
+    /// `?1` to `?4` was used, but that charset wasn't given.
+    UndefinedCharset(usize),
+    /// Custom charsets can only be made of built-in ones and literals.
+    NestedCharset(usize),
+    /// The mask is shorter than the length to increment from.
+    TooShort,
+    /// The keyspace doesn't fit in 64 bits.
+    TooLarge,
+}
+
+impl fmt::Display for MaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "The mask is empty"),
+            Self::UnknownCharset(name) => write!(f, "Unknown charset '?{}'", name),
+            Self::DanglingQuestionMark => write!(f, "The mask ends in a lone '?'"),
+            Self::UndefinedCharset(index) => write!(f, "Custom charset ?{} isn't set", index),
+            Self::NestedCharset(index) => {
+                write!(f, "Custom charsets can't use ?{}", index)
+            }
+            Self::TooShort => write!(f, "The mask is shorter than the minimum length"),
+            Self::TooLarge => write!(f, "The keyspace is too large"),
+        }
+    }
+}
+
+impl Error for MaskError {}
+
+/// Expands a mask or charset definition like `?l?u_-` into the bytes each of
+/// its positions can take.
+///
+/// `custom` holds the already expanded custom charsets, for masks. Custom
+/// charset definitions themselves are expanded without any.
+fn expand(definition: &str, custom: Option<&[Option<Vec<u8>>]>) -> Result<Vec<Vec<u8>>, MaskError> {
+    let mut positions = Vec::new();
+    let mut bytes = definition.bytes();
+
+    while let Some(byte) = bytes.next() {
+        if byte != b'?' {
+            positions.push(vec![byte]);
+            continue;
+        }
+
+        let name = bytes.next().ok_or(MaskError::DanglingQuestionMark)?;
+        let charset = match name {
+            b'l' => LOWER.to_vec(),
+            b'u' => UPPER.to_vec(),
+            b'd' => DIGITS.to_vec(),
+            b's' => SPECIAL.to_vec(),
+            b'a' => [LOWER, UPPER, DIGITS, SPECIAL].concat(),
+            b'h' => HEX_LOWER.to_vec(),
+            b'H' => HEX_UPPER.to_vec(),
+            b'b' => (0..=u8::MAX).collect(),
+            b'?' => vec![b'?'],
+            b'1'..=b'4' => {
+                let index = (name - b'0') as usize;
+                match custom {
+                    Some(custom) => custom[index - 1]
+                        .clone()
+                        .ok_or(MaskError::UndefinedCharset(index))?,
+                    None => return Err(MaskError::NestedCharset(index)),
+                }
+            }
+            _ => return Err(MaskError::UnknownCharset(name as char)),
+        };
+        positions.push(charset);
+    }
+
+    Ok(positions)
+}
+
+/// A mask such as `?l?l?l?l?d?d?d?d`, with one charset per position.
+///
+/// Candidates are numbered from 0 to the [`keyspace`](Self::keyspace), with
+/// the last position changing fastest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mask {
+    positions: Vec<Vec<u8>>,
+    keyspace: u64,
+}
+
+impl Mask {
+    fn new(positions: Vec<Vec<u8>>) -> Result<Self, MaskError> {
+        if positions.is_empty() {
+            return Err(MaskError::Empty);
+        }
+        let keyspace = positions
+            .iter()
+            .try_fold(1u64, |keyspace, charset| {
+                keyspace.checked_mul(charset.len() as u64)
+            })
+            .ok_or(MaskError::TooLarge)?;
+        Ok(Self {
+            positions,
+            keyspace,
+        })
+    }
+
+    /// Parses `mask`, where `charsets` are the definitions of `?1` to `?4`.
+    pub fn parse(mask: &str, charsets: &[Option<&str>]) -> Result<Self, MaskError> {
+        let mut custom = vec![None; CUSTOM_CHARSETS];
+        for (slot, definition) in custom.iter_mut().zip(charsets) {
+            if let Some(definition) = definition {
+                let mut charset = Vec::new();
+                for byte in expand(definition, None)?.into_iter().flatten() {
+                    if !charset.contains(&byte) {
+                        charset.push(byte);
+                    }
+                }
+                *slot = Some(charset);
+            }
+        }
+        Self::new(expand(mask, Some(&custom))?)
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// How many candidates the mask makes.
+    pub fn keyspace(&self) -> u64 {
+        self.keyspace
+    }
+
+    /// The mask cut down to its first `len` positions.
+    pub fn prefix(&self, len: usize) -> Result<Self, MaskError> {
+        Self::new(self.positions[..len.min(self.len())].to_vec())
+    }
+
+    /// The `index`th candidate, for `index` below the keyspace.
+    pub fn candidate(&self, mut index: u64) -> Vec<u8> {
+        let mut candidate = vec![0; self.positions.len()];
+        for (byte, charset) in candidate.iter_mut().zip(&self.positions).rev() {
+            let base = charset.len() as u64;
+            *byte = charset[(index % base) as usize];
+            index /= base;
+        }
+        candidate
+    }
+}
+
+/// Everything that describes a mask attack, as entered by the user.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaskSpec {
+    pub mask: String,
+    /// Definitions of `?1` to `?4`, in order. Missing or empty ones are
+    /// unset.
+    #[serde(default)]
+    pub charsets: Vec<String>,
+    /// Also try the mask cut down to these lengths, shortest first, like
+    /// hashcat's `--increment`. Lengths past the end of the mask are left
+    /// out.
+    #[serde(default)]
+    pub increment: Option<RangeInclusive<usize>>,
+}
+
+impl MaskSpec {
+    /// The masks to try, in order. Just the one, unless incrementing.
+    ///
+    /// Lengths are those of the mask itself, so for a hybrid attack they
+    /// don't count the word it is joined to.
+    pub fn masks(&self) -> Result<Vec<Mask>, MaskError> {
+        let charsets: Vec<Option<&str>> = self
+            .charsets
+            .iter()
+            .map(|charset| Some(charset.as_str()).filter(|charset| !charset.is_empty()))
+            .collect();
+        let mask = Mask::parse(&self.mask, &charsets)?;
+
+        match &self.increment {
+            None => Ok(vec![mask]),
+            Some(lengths) => {
+                let min = (*lengths.start()).max(1);
+                let max = (*lengths.end()).min(mask.len());
+                if min > max {
+                    return Err(MaskError::TooShort);
+                }
+                (min..=max).map(|len| mask.prefix(len)).collect()
+            }
+        }
+    }
+
+    /// The masks to try for a mask attack on its own. They are those of
+    /// [`masks`](Self::masks), less the lengths of the increment that are
+    /// too short for a passphrase, which hashcat leaves out for WPA too.
+    pub fn passphrase_masks(&self) -> Result<Vec<Mask>, MaskError> {
+        let masks = self.masks()?;
+        if self.increment.is_none() {
+            return Ok(masks);
+        }
+        let masks: Vec<Mask> = masks
+            .into_iter()
+            .filter(|mask| mask.len() >= MIN_PASSPHRASE_LEN)
+            .collect();
+        if masks.is_empty() {
+            return Err(MaskError::TooShort);
+        }
+        Ok(masks)
+    }
+
+    /// The lengths of the increment that
+    /// [`passphrase_masks`](Self::passphrase_masks) leaves out, shortest
+    /// first, so that the user can be told.
+    pub fn too_short_lengths(&self) -> Result<Vec<usize>, MaskError> {
+        if self.increment.is_none() {
+            return Ok(Vec::new());
+        }
+        Ok(self
+            .masks()?
+            .iter()
+            .map(Mask::len)
+            .filter(|&len| len < MIN_PASSPHRASE_LEN)
+            .collect())
+    }
+
+    /// How many candidates the masks of a hybrid attack make, for each word.
+    pub fn keyspace(&self) -> Result<u64, MaskError> {
+        total_keyspace(&self.masks()?)
+    }
+
+    /// How many candidates a mask attack on its own makes.
+    pub fn passphrase_keyspace(&self) -> Result<u64, MaskError> {
+        total_keyspace(&self.passphrase_masks()?)
+    }
+}
+
+fn total_keyspace(masks: &[Mask]) -> Result<u64, MaskError> {
+    masks
+        .iter()
+        .try_fold(0u64, |keyspace, mask| keyspace.checked_add(mask.keyspace()))
+        .ok_or(MaskError::TooLarge)
+}
+
+/// Generates the candidates of a [`Mask`] lazily, a batch at a time, spread
+/// over the thread pool.
+///
+/// Progress and positions count candidates. The masks of an increment are
+/// meant to be pushed to a [`WordlistQueue`](super::WordlistQueue) in order.
+#[derive(Debug)]
+pub struct MaskCandidates {
+    mask: Mask,
+    next: u64,
+}
+
+impl MaskCandidates {
+    pub fn new(mask: Mask) -> Self {
+        Self { mask, next: 0 }
+    }
+}
+
+impl CandidateSource for MaskCandidates {
+    fn total(&self) -> u64 {
+        self.mask.keyspace()
+    }
+
+    fn progress(&self) -> u64 {
+        self.next
+    }
+
+    fn next_batch(&mut self) -> io::Result<Option<Batch>> {
+        let end = (self.next + BATCH_SIZE).min(self.mask.keyspace());
+        if self.next >= end {
+            return Ok(None);
+        }
+
+        let mask = &self.mask;
+        let candidates = (self.next..end)
+            .into_par_iter()
+            .map(|index| mask.candidate(index))
+            .collect();
+        self.next = end;
+
+        Ok(Some(Batch {
+            candidates,
+            end: Position {
+                source: 0,
+                offset: end,
+            },
+            progress: end,
+        }))
+    }
+
+    fn skip_to(&mut self, position: Position) -> io::Result<()> {
+        self.next = if position.source > 0 {
+            self.mask.keyspace()
+        } else {
+            position.offset.min(self.mask.keyspace()).max(self.next)
+        };
+        Ok(())
+    }
+}
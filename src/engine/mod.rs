@@ -0,0 +1,113 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! WPA2-PSK PMKID cracking engine.
+//!
+//! A [`Cracker`] derives the PMK of every candidate coming out of a
+//! [`CandidateSource`] and checks it against a [`PmkidTarget`], keeping a
+//! [`Status`] that can be polled as it goes. Nothing in here knows about GTK.
+
+mod benchmark;
+mod checkpoint;
+mod combinator;
+mod control;
+mod cracker;
+mod filter;
+mod history;
+mod hybrid;
+mod mask;
+mod pairs;
+mod pbkdf2;
+mod potfile;
+mod queue;
+mod rules;
+mod status;
+mod target;
+mod wordlist;
+
+pub use benchmark::{Benchmark, BenchmarkPoint, BENCHMARK_STEP};
+pub use checkpoint::{Attack, Checkpoint, Checkpointer, FileIdentity};
+pub use combinator::{collect_candidates, count_candidates, Combinator};
+pub use control::{CancelToken, PauseToken, SkipToken};
+pub use cracker::{Cracker, NoTargets, Outcome};
+pub use filter::{CandidateFilter, SkipStats, MAX_PASSPHRASE_LEN, MIN_PASSPHRASE_LEN, RAW_PSK_LEN};
+pub use history::{History, HistoryEntry, HistoryOutcome};
+pub use hybrid::{Hybrid, HybridSide};
+pub use mask::{Mask, MaskCandidates, MaskError, MaskSpec, CUSTOM_CHARSETS};
+#[cfg(feature = "backend-fastpbkdf2")]
+pub use pbkdf2::FastPbkdf2;
+#[cfg(feature = "backend-rustcrypto")]
+pub use pbkdf2::RustCrypto;
+pub use pbkdf2::{DefaultPbkdf2, Pbkdf2Backend, Pbkdf2Sha1, MAX_LANES};
+pub use potfile::Potfile;
+pub use queue::WordlistQueue;
+pub use rules::{Mangled, Rule, RuleError, RuleSet};
+pub use status::{Status, StatusHandle, STATUS_INTERVAL};
+pub use target::{
+    format_hash_lines, read_hash_lines, HashLineError, NetworkParams, PmkidTarget, TargetError,
+};
+pub use wordlist::{
+    decode_candidate, format_passphrase, Batch, CandidateSource, Compression, Position, Wordlist,
+    MAX_LINE_LEN,
+};
+
+use serde::Serialize;
+
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Where Ramsh keeps its own files, following the XDG base directory spec.
+///
+/// This is the same place `glib::user_data_dir()` points to, so the
+/// application and `ramsh-cli` share their files.
+pub fn data_dir() -> PathBuf {
+    let base = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .unwrap_or_else(env::temp_dir);
+    base.join("ramsh")
+}
+
+/// The potfile the application and `ramsh-cli` share, unless told otherwise.
+pub fn potfile_path() -> PathBuf {
+    data_dir().join("ramsh.potfile")
+}
+
+/// Where the result of the last [`Benchmark`] is kept.
+pub fn benchmark_path() -> PathBuf {
+    data_dir().join("benchmark.json")
+}
+
+/// Writes a duration as precisely as is worth reading, e.g. `850 ms` or
+/// `2 h 5 min`.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, mins, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{} h {} min", hours, mins)
+    } else if mins > 0 {
+        format!("{} min {} s", mins, secs)
+    } else if secs > 0 {
+        format!("{} s", secs)
+    } else {
+        format!("{} ms", duration.as_millis())
+    }
+}
+
+/// Writes `value` to `path` as JSON, creating the directory if needed.
+///
+/// The file is written next to `path` and renamed over it, so that a crash
+/// mid-write doesn't leave a truncated file behind.
+fn save_json<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    let file = File::create(&tmp_path)?;
+    serde_json::to_writer_pretty(file, value)?;
+    fs::rename(tmp_path, path)
+}
@@ -0,0 +1,133 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use rayon::prelude::*;
+
+use std::collections::VecDeque;
+use std::io;
+
+use super::{Batch, CandidateSource, Position};
+
+/// The most candidates handed out in one [`Batch`].
+const BATCH_SIZE: usize = 4096;
+
+/// Pairs every word of a source with `width` parts, one word after the other,
+/// for attacks such as [`Hybrid`](super::Hybrid) that multiply a wordlist.
+///
+/// Progress and positions count the pairs, out of the number of words times
+/// `width`, so the number of words has to be known up front. Pairs left out
+/// of a batch still count.
+#[derive(Debug)]
+pub(crate) struct Pairs<S> {
+    source: S,
+    width: u64,
+    words: u64,
+    /// Words read from the source that aren't done pairing yet.
+    pending: VecDeque<Vec<u8>>,
+    /// Index of the next part for the first pending word.
+    next: u64,
+    /// Pairs handed out so far.
+    done: u64,
+}
+
+impl<S: CandidateSource> Pairs<S> {
+    pub fn new(source: S, width: u64, words: u64) -> Self {
+        Self {
+            source,
+            width,
+            words,
+            pending: VecDeque::new(),
+            next: 0,
+            done: 0,
+        }
+    }
+
+    /// Makes sure there is a pending word, reading more from the source if
+    /// needed. Returns false once the source is exhausted.
+    fn fill_pending(&mut self) -> io::Result<bool> {
+        while self.pending.is_empty() {
+            match self.source.next_batch()? {
+                Some(batch) => self.pending.extend(batch.candidates),
+                None => return Ok(false),
+            }
+        }
+        Ok(true)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.words.saturating_mul(self.width)
+    }
+
+    pub fn progress(&self) -> u64 {
+        // The word count is off if the list changed since it was counted
+        self.done.min(self.total())
+    }
+
+    /// The next batch of pairs, each made by `pair` out of a word and the
+    /// index of a part, or left out if it returns `None`. Pairs are made in
+    /// parallel.
+    pub fn next_batch<F>(&mut self, pair: F) -> io::Result<Option<Batch>>
+    where
+        F: Fn(&[u8], u64) -> Option<Vec<u8>> + Sync,
+    {
+        if self.width == 0 {
+            return Ok(None);
+        }
+
+        // Pairs left out count towards the size of the batch too, so that
+        // checkpoints keep up when most of them are
+        let start = self.done;
+        let mut candidates = Vec::with_capacity(BATCH_SIZE);
+        while self.done - start < BATCH_SIZE as u64 && self.fill_pending()? {
+            let end = (self.next + BATCH_SIZE as u64 - (self.done - start)).min(self.width);
+            let word = &self.pending[0];
+            candidates.par_extend(
+                (self.next..end)
+                    .into_par_iter()
+                    .filter_map(|index| pair(word, index)),
+            );
+
+            self.done += end - self.next;
+            self.next = end;
+            if self.next == self.width {
+                self.pending.pop_front();
+                self.next = 0;
+            }
+        }
+
+        if self.done == start {
+            return Ok(None);
+        }
+        Ok(Some(Batch {
+            candidates,
+            end: Position {
+                source: 0,
+                offset: self.done,
+            },
+            progress: self.progress(),
+        }))
+    }
+
+    pub fn skip_to(&mut self, position: Position) -> io::Result<()> {
+        if position.source > 0 {
+            self.pending.clear();
+            self.done = self.done.max(self.total());
+            return self.source.skip_to(position);
+        }
+
+        // Words have to be read to be skipped, but reading is cheap next to
+        // deriving a PMK for each of their pairs
+        while self.done < position.offset && self.width > 0 && self.fill_pending()? {
+            let word_end = self.done - self.next + self.width;
+            if position.offset >= word_end {
+                self.pending.pop_front();
+                self.done = word_end;
+                self.next = 0;
+            } else {
+                self.next += position.offset - self.done;
+                self.done = position.offset;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,467 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! PBKDF2-HMAC-SHA1 for many passphrases at once.
+//!
+//! Deriving a PMK takes two PBKDF2 blocks of 4096 iterations each, every
+//! iteration being two SHA-1 compressions that depend on the one before.
+//! A [`Pbkdf2Sha1`] library runs them one passphrase at a time. The other backends
+//! run the chains of several passphrases side by side, one per lane of a
+//! SIMD register, or interleaved on the SHA extensions.
+
+mod scalar;
+#[cfg(target_arch = "x86_64")]
+mod x86;
+
+#[cfg(feature = "backend-fastpbkdf2")]
+pub use scalar::FastPbkdf2;
+#[cfg(feature = "backend-rustcrypto")]
+pub use scalar::RustCrypto;
+pub use scalar::{DefaultPbkdf2, Pbkdf2Sha1};
+
+use std::fmt;
+
+/// How many times the HMAC is iterated for each block of the PMK.
+pub(crate) const ITERATIONS: usize = 4096;
+
+/// The most PMKs any [`Pbkdf2Backend`] derives at once.
+pub const MAX_LANES: usize = 16;
+
+const SHA1_IV: [u32; 5] = [
+    0x6745_2301,
+    0xEFCD_AB89,
+    0x98BA_DCFE,
+    0x1032_5476,
+    0xC3D2_E1F0,
+];
+
+/// A way of deriving PMKs, see [`Pbkdf2Backend::detect`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pbkdf2Backend {
+    /// One passphrase at a time through [`DefaultPbkdf2`].
+    Scalar,
+    /// 4 passphrases at once in SSE2 registers.
+    Sse2,
+    /// 8 passphrases at once in AVX2 registers.
+    Avx2,
+    /// 16 passphrases at once in AVX-512 registers.
+    Avx512,
+    /// One passphrase at a time on the SHA extensions, with both blocks of
+    /// its PMK interleaved.
+    ShaNi,
+}
+
+impl Pbkdf2Backend {
+    /// Every backend, from the slowest to the fastest on most machines.
+    pub const ALL: [Self; 5] = [
+        Self::Scalar,
+        Self::Sse2,
+        Self::ShaNi,
+        Self::Avx2,
+        Self::Avx512,
+    ];
+
+    /// The fastest backend this CPU supports.
+    pub fn detect() -> Self {
+        Self::ALL
+            .into_iter()
+            .rev()
+            .find(|backend| backend.is_supported())
+            .unwrap_or(Self::Scalar)
+    }
+
+    /// Whether this CPU has the instructions the backend needs.
+    pub fn is_supported(self) -> bool {
+        match self {
+            Self::Scalar => true,
+            #[cfg(target_arch = "x86_64")]
+            Self::Sse2 => is_x86_feature_detected!("sse2"),
+            #[cfg(target_arch = "x86_64")]
+            Self::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(target_arch = "x86_64")]
+            Self::Avx512 => is_x86_feature_detected!("avx512f"),
+            #[cfg(target_arch = "x86_64")]
+            Self::ShaNi => {
+                is_x86_feature_detected!("sha")
+                    && is_x86_feature_detected!("sse4.1")
+                    && is_x86_feature_detected!("ssse3")
+            }
+            #[cfg(not(target_arch = "x86_64"))]
+            _ => false,
+        }
+    }
+
+    /// The name used on the command line, e.g. `avx2`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Scalar => "scalar",
+            Self::Sse2 => "sse2",
+            Self::Avx2 => "avx2",
+            Self::Avx512 => "avx512",
+            Self::ShaNi => "sha-ni",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|backend| backend.name() == name)
+    }
+
+    /// How many passphrases are worth handing to
+    /// [`derive_pmks`](Self::derive_pmks) at once. Fewer work too, but leave
+    /// lanes idle.
+    pub fn lanes(self) -> usize {
+        match self {
+            Self::Scalar | Self::ShaNi => 1,
+            Self::Sse2 => 4,
+            Self::Avx2 => 8,
+            Self::Avx512 => MAX_LANES,
+        }
+    }
+
+    /// Derives the PMK of each of `passphrases` on the network `essid`,
+    /// into the matching entry of `pmks`.
+    ///
+    /// # Panics
+    ///
+    /// If there are more than [`lanes`](Self::lanes) passphrases, more than
+    /// `pmks`, or the backend [isn't supported](Self::is_supported).
+    pub fn derive_pmks(self, essid: &[u8], passphrases: &[&[u8]], pmks: &mut [[u8; 32]]) {
+        assert!(passphrases.len() <= self.lanes() && passphrases.len() <= pmks.len());
+        assert!(self.is_supported(), "{} isn't supported by this CPU", self);
+
+        if self == Self::Scalar {
+            for (passphrase, pmk) in passphrases.iter().zip(pmks) {
+                DefaultPbkdf2::pbkdf2(passphrase, essid, ITERATIONS as u32, pmk);
+            }
+            return;
+        }
+
+        // Both blocks of every PMK are independent chains
+        let mut chains = [Chain::default(); 2 * MAX_LANES];
+        let chains = &mut chains[..2 * passphrases.len()];
+        for (passphrase, pair) in passphrases.iter().zip(chains.chunks_mut(2)) {
+            let (ipad, opad) = hmac_keys(passphrase);
+            for (block, chain) in pair.iter_mut().enumerate() {
+                *chain = Chain::new(ipad, opad, essid, block as u32 + 1);
+            }
+        }
+
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: the CPU has the features, checked above
+        unsafe {
+            match self {
+                Self::Scalar => unreachable!(),
+                Self::Sse2 => x86::iterate_sse2(chains),
+                Self::Avx2 => x86::iterate_avx2(chains),
+                Self::Avx512 => x86::iterate_avx512(chains),
+                Self::ShaNi => x86::iterate_sha_ni(chains),
+            }
+        }
+
+        for (pair, pmk) in chains.chunks(2).zip(pmks) {
+            let words = pair[0].t.iter().chain(&pair[1].t[..3]);
+            for (bytes, word) in pmk.chunks_mut(4).zip(words) {
+                bytes.copy_from_slice(&word.to_be_bytes());
+            }
+        }
+    }
+}
+
+impl fmt::Display for Pbkdf2Backend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Scalar => "Scalar",
+            Self::Sse2 => "SSE2",
+            Self::Avx2 => "AVX2",
+            Self::Avx512 => "AVX-512",
+            Self::ShaNi => "SHA-NI",
+        })
+    }
+}
+
+/// One block of a PMK, part way through its iterations.
+///
+/// Only the x86 backends iterate chains, elsewhere they are set up and never
+/// run.
+#[cfg_attr(not(target_arch = "x86_64"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, Default)]
+struct Chain {
+    /// The SHA-1 state after the inner and outer HMAC key blocks.
+    ipad: [u32; 5],
+    opad: [u32; 5],
+    /// The output of the last iteration.
+    u: [u32; 5],
+    /// The outputs of every iteration so far XORed together, which is the
+    /// block once all of them are done.
+    t: [u32; 5],
+}
+
+impl Chain {
+    /// The chain of the `block`th block, with its first iteration done.
+    fn new(ipad: [u32; 5], opad: [u32; 5], essid: &[u8], block: u32) -> Self {
+        let mut message = essid.to_vec();
+        message.extend_from_slice(&block.to_be_bytes());
+        let inner = sha1_finish(ipad, &message);
+        let u = hash20(opad, inner);
+        Self {
+            ipad,
+            opad,
+            u,
+            t: u,
+        }
+    }
+}
+
+/// The SHA-1 states after the inner and outer key blocks of an HMAC keyed
+/// with `key`.
+fn hmac_keys(key: &[u8]) -> ([u32; 5], [u32; 5]) {
+    let mut block = [0; 64];
+    if key.len() > block.len() {
+        block[..20].copy_from_slice(&sha1_smol::Sha1::from(key).digest().bytes());
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let pad = |byte: u8| {
+        let mut state = SHA1_IV;
+        let mut padded = block;
+        padded.iter_mut().for_each(|b| *b ^= byte);
+        compress_block(&mut state, &padded);
+        state
+    };
+    (pad(0x36), pad(0x5c))
+}
+
+/// Finishes a SHA-1 that has gone through one 64 byte block to get `state`,
+/// with `message` as the rest of the input.
+fn sha1_finish(mut state: [u32; 5], message: &[u8]) -> [u32; 5] {
+    let bits = (64 + message.len() as u64) * 8;
+    let mut tail = message.to_vec();
+    tail.push(0x80);
+    while tail.len() % 64 != 56 {
+        tail.push(0);
+    }
+    tail.extend_from_slice(&bits.to_be_bytes());
+
+    for block in tail.chunks(64) {
+        compress_block(&mut state, block.try_into().unwrap());
+    }
+    state
+}
+
+fn compress_block(state: &mut [u32; 5], block: &[u8; 64]) {
+    let mut w = [0; 16];
+    for (word, bytes) in w.iter_mut().zip(block.chunks(4)) {
+        *word = u32::from_be_bytes(bytes.try_into().unwrap());
+    }
+    compress(state, &mut w);
+}
+
+/// SHA-1 of a 20 byte message, such as another SHA-1, following a 64 byte
+/// key block that got the hash to `state`.
+#[inline(always)]
+fn hash20<W: Word>(mut state: [W; 5], message: [W; 5]) -> [W; 5] {
+    let zero = W::splat(0);
+    let mut w = [
+        message[0],
+        message[1],
+        message[2],
+        message[3],
+        message[4],
+        W::splat(0x8000_0000),
+        zero,
+        zero,
+        zero,
+        zero,
+        zero,
+        zero,
+        zero,
+        zero,
+        zero,
+        W::splat((64 + 20) * 8),
+    ];
+    compress(&mut state, &mut w);
+    state
+}
+
+/// One or more 32 bit words, one per lane, and what SHA-1 does with them.
+///
+/// Lanes only matter to [`iterate_lanes`], so outside x86 just `u32` is
+/// used, for the first iteration of each chain.
+#[cfg_attr(not(target_arch = "x86_64"), allow(dead_code))]
+trait Word: Copy {
+    /// How many lanes there are.
+    const LANES: usize;
+
+    fn splat(value: u32) -> Self;
+    /// Takes the first [`LANES`](Self::LANES) of `values`.
+    fn load(values: &[u32]) -> Self;
+    /// Writes to the first [`LANES`](Self::LANES) of `values`.
+    fn store(self, values: &mut [u32]);
+
+    fn add(self, other: Self) -> Self;
+    fn xor(self, other: Self) -> Self;
+    fn and(self, other: Self) -> Self;
+    fn or(self, other: Self) -> Self;
+    fn rotate_left(self, bits: u32) -> Self;
+
+    #[inline(always)]
+    fn choose(b: Self, c: Self, d: Self) -> Self {
+        d.xor(b.and(c.xor(d)))
+    }
+
+    #[inline(always)]
+    fn parity(b: Self, c: Self, d: Self) -> Self {
+        b.xor(c).xor(d)
+    }
+
+    #[inline(always)]
+    fn majority(b: Self, c: Self, d: Self) -> Self {
+        b.and(c).or(d.and(b.or(c)))
+    }
+}
+
+impl Word for u32 {
+    const LANES: usize = 1;
+
+    #[inline(always)]
+    fn splat(value: u32) -> Self {
+        value
+    }
+
+    #[inline(always)]
+    fn load(values: &[u32]) -> Self {
+        values[0]
+    }
+
+    #[inline(always)]
+    fn store(self, values: &mut [u32]) {
+        values[0] = self;
+    }
+
+    #[inline(always)]
+    fn add(self, other: Self) -> Self {
+        self.wrapping_add(other)
+    }
+
+    #[inline(always)]
+    fn xor(self, other: Self) -> Self {
+        self ^ other
+    }
+
+    #[inline(always)]
+    fn and(self, other: Self) -> Self {
+        self & other
+    }
+
+    #[inline(always)]
+    fn or(self, other: Self) -> Self {
+        self | other
+    }
+
+    #[inline(always)]
+    fn rotate_left(self, bits: u32) -> Self {
+        u32::rotate_left(self, bits)
+    }
+}
+
+/// One round of SHA-1, with the roles of the state words passed in so that
+/// they don't have to be shuffled around. The message schedule is worked
+/// out in place in `w`.
+macro_rules! round {
+    ($a:ident, $b:ident, $c:ident, $d:ident, $e:ident, $w:ident, $f:ident, $k:expr, $t:expr) => {
+        if $t >= 16 {
+            $w[$t % 16] = $w[($t + 13) % 16]
+                .xor($w[($t + 8) % 16])
+                .xor($w[($t + 2) % 16])
+                .xor($w[$t % 16])
+                .rotate_left(1);
+        }
+        $e = $e
+            .add($a.rotate_left(5))
+            .add(W::$f($b, $c, $d))
+            .add(W::splat($k))
+            .add($w[$t % 16]);
+        $b = $b.rotate_left(30);
+    };
+}
+
+/// Five rounds, after which the state words are back in their roles.
+macro_rules! rounds5 {
+    ($a:ident, $b:ident, $c:ident, $d:ident, $e:ident, $w:ident, $f:ident, $k:expr, $t:expr) => {
+        round!($a, $b, $c, $d, $e, $w, $f, $k, $t);
+        round!($e, $a, $b, $c, $d, $w, $f, $k, $t + 1);
+        round!($d, $e, $a, $b, $c, $w, $f, $k, $t + 2);
+        round!($c, $d, $e, $a, $b, $w, $f, $k, $t + 3);
+        round!($b, $c, $d, $e, $a, $w, $f, $k, $t + 4);
+    };
+}
+
+/// The SHA-1 compression function, on every lane of `W` at once. The
+/// message block is overwritten.
+#[inline(always)]
+fn compress<W: Word>(state: &mut [W; 5], w: &mut [W; 16]) {
+    let [mut a, mut b, mut c, mut d, mut e] = *state;
+
+    rounds5!(a, b, c, d, e, w, choose, 0x5A82_7999, 0);
+    rounds5!(a, b, c, d, e, w, choose, 0x5A82_7999, 5);
+    rounds5!(a, b, c, d, e, w, choose, 0x5A82_7999, 10);
+    rounds5!(a, b, c, d, e, w, choose, 0x5A82_7999, 15);
+    rounds5!(a, b, c, d, e, w, parity, 0x6ED9_EBA1, 20);
+    rounds5!(a, b, c, d, e, w, parity, 0x6ED9_EBA1, 25);
+    rounds5!(a, b, c, d, e, w, parity, 0x6ED9_EBA1, 30);
+    rounds5!(a, b, c, d, e, w, parity, 0x6ED9_EBA1, 35);
+    rounds5!(a, b, c, d, e, w, majority, 0x8F1B_BCDC, 40);
+    rounds5!(a, b, c, d, e, w, majority, 0x8F1B_BCDC, 45);
+    rounds5!(a, b, c, d, e, w, majority, 0x8F1B_BCDC, 50);
+    rounds5!(a, b, c, d, e, w, majority, 0x8F1B_BCDC, 55);
+    rounds5!(a, b, c, d, e, w, parity, 0xCA62_C1D6, 60);
+    rounds5!(a, b, c, d, e, w, parity, 0xCA62_C1D6, 65);
+    rounds5!(a, b, c, d, e, w, parity, 0xCA62_C1D6, 70);
+    rounds5!(a, b, c, d, e, w, parity, 0xCA62_C1D6, 75);
+
+    for (word, new) in state.iter_mut().zip([a, b, c, d, e]) {
+        *word = word.add(new);
+    }
+}
+
+/// Runs the remaining iterations of `chains`, [`W::LANES`](Word::LANES) at
+/// a time. Has to be inlined into a function with the target features `W`
+/// needs.
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+fn iterate_lanes<W: Word>(chains: &mut [Chain]) {
+    for group in chains.chunks_mut(W::LANES) {
+        // A short group repeats its last chain in the spare lanes
+        let gather = |word: fn(&Chain) -> &[u32; 5]| {
+            let mut state = [W::splat(0); 5];
+            for (i, lanes) in state.iter_mut().enumerate() {
+                let mut values = [0; MAX_LANES];
+                for (lane, value) in values[..W::LANES].iter_mut().enumerate() {
+                    *value = word(&group[lane.min(group.len() - 1)])[i];
+                }
+                *lanes = W::load(&values);
+            }
+            state
+        };
+        let ipad = gather(|chain| &chain.ipad);
+        let opad = gather(|chain| &chain.opad);
+        let mut u = gather(|chain| &chain.u);
+        let mut t = gather(|chain| &chain.t);
+
+        for _ in 1..ITERATIONS {
+            u = hash20(opad, hash20(ipad, u));
+            for (t, u) in t.iter_mut().zip(u) {
+                *t = t.xor(u);
+            }
+        }
+
+        for (i, lanes) in t.into_iter().enumerate() {
+            let mut values = [0; MAX_LANES];
+            lanes.store(&mut values);
+            for (chain, value) in group.iter_mut().zip(values) {
+                chain.t[i] = value;
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The libraries PBKDF2 and HMAC come from, one input at a time, picked
+//! with cargo features. `backend-fastpbkdf2`, the default, builds C code;
+//! `backend-rustcrypto` is pure Rust, for targets that can't build it.
+
+#[cfg(not(any(feature = "backend-fastpbkdf2", feature = "backend-rustcrypto")))]
+compile_error!("enable the `backend-fastpbkdf2` or `backend-rustcrypto` feature");
+
+/// PBKDF2-HMAC-SHA1 and HMAC-SHA1 from some library.
+pub trait Pbkdf2Sha1 {
+    /// Fills `out` with PBKDF2-HMAC-SHA1 of `password` and `salt`.
+    fn pbkdf2(password: &[u8], salt: &[u8], rounds: u32, out: &mut [u8]);
+
+    fn hmac(key: &[u8], message: &[u8]) -> [u8; 20];
+}
+
+/// The [`fastpbkdf2`] and [`hmacsha1`] crates.
+#[cfg(feature = "backend-fastpbkdf2")]
+#[derive(Debug, Clone, Copy)]
+pub struct FastPbkdf2;
+
+#[cfg(feature = "backend-fastpbkdf2")]
+impl Pbkdf2Sha1 for FastPbkdf2 {
+    fn pbkdf2(password: &[u8], salt: &[u8], rounds: u32, out: &mut [u8]) {
+        fastpbkdf2::pbkdf2_hmac_sha1(password, salt, rounds, out);
+    }
+
+    fn hmac(key: &[u8], message: &[u8]) -> [u8; 20] {
+        hmacsha1::hmac_sha1(key, message)
+    }
+}
+
+/// The [`pbkdf2`], [`hmac`] and [`sha1`] crates of RustCrypto.
+#[cfg(feature = "backend-rustcrypto")]
+#[derive(Debug, Clone, Copy)]
+pub struct RustCrypto;
+
+#[cfg(feature = "backend-rustcrypto")]
+impl Pbkdf2Sha1 for RustCrypto {
+    fn pbkdf2(password: &[u8], salt: &[u8], rounds: u32, out: &mut [u8]) {
+        pbkdf2::pbkdf2::<hmac::Hmac<sha1::Sha1>>(password, salt, rounds, out);
+    }
+
+    fn hmac(key: &[u8], message: &[u8]) -> [u8; 20] {
+        use hmac::Mac;
+
+        // Any length of key will do for an HMAC
+        let mut mac = hmac::Hmac::<sha1::Sha1>::new_from_slice(key).unwrap();
+        mac.update(message);
+        mac.finalize().into_bytes().into()
+    }
+}
+
+/// What [`Pbkdf2Backend::Scalar`](super::Pbkdf2Backend::Scalar) and the
+/// PMKID check use, fastpbkdf2 if both features are on.
+#[cfg(feature = "backend-fastpbkdf2")]
+pub type DefaultPbkdf2 = FastPbkdf2;
+
+#[cfg(all(feature = "backend-rustcrypto", not(feature = "backend-fastpbkdf2")))]
+pub type DefaultPbkdf2 = RustCrypto;
@@ -0,0 +1,341 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The x86-64 backends. Each entry point is only safe to call once the CPU
+//! is known to have its target features.
+
+use std::arch::x86_64::*;
+
+use super::{iterate_lanes, Chain, Word, ITERATIONS};
+
+impl Word for __m128i {
+    const LANES: usize = 4;
+
+    #[inline(always)]
+    fn splat(value: u32) -> Self {
+        unsafe { _mm_set1_epi32(value as i32) }
+    }
+
+    #[inline(always)]
+    fn load(values: &[u32]) -> Self {
+        let values = &values[..Self::LANES];
+        unsafe { _mm_loadu_si128(values.as_ptr().cast()) }
+    }
+
+    #[inline(always)]
+    fn store(self, values: &mut [u32]) {
+        let values = &mut values[..Self::LANES];
+        unsafe { _mm_storeu_si128(values.as_mut_ptr().cast(), self) }
+    }
+
+    #[inline(always)]
+    fn add(self, other: Self) -> Self {
+        unsafe { _mm_add_epi32(self, other) }
+    }
+
+    #[inline(always)]
+    fn xor(self, other: Self) -> Self {
+        unsafe { _mm_xor_si128(self, other) }
+    }
+
+    #[inline(always)]
+    fn and(self, other: Self) -> Self {
+        unsafe { _mm_and_si128(self, other) }
+    }
+
+    #[inline(always)]
+    fn or(self, other: Self) -> Self {
+        unsafe { _mm_or_si128(self, other) }
+    }
+
+    #[inline(always)]
+    fn rotate_left(self, bits: u32) -> Self {
+        unsafe {
+            _mm_or_si128(
+                _mm_sll_epi32(self, _mm_cvtsi32_si128(bits as i32)),
+                _mm_srl_epi32(self, _mm_cvtsi32_si128(32 - bits as i32)),
+            )
+        }
+    }
+}
+
+impl Word for __m256i {
+    const LANES: usize = 8;
+
+    #[inline(always)]
+    fn splat(value: u32) -> Self {
+        unsafe { _mm256_set1_epi32(value as i32) }
+    }
+
+    #[inline(always)]
+    fn load(values: &[u32]) -> Self {
+        let values = &values[..Self::LANES];
+        unsafe { _mm256_loadu_si256(values.as_ptr().cast()) }
+    }
+
+    #[inline(always)]
+    fn store(self, values: &mut [u32]) {
+        let values = &mut values[..Self::LANES];
+        unsafe { _mm256_storeu_si256(values.as_mut_ptr().cast(), self) }
+    }
+
+    #[inline(always)]
+    fn add(self, other: Self) -> Self {
+        unsafe { _mm256_add_epi32(self, other) }
+    }
+
+    #[inline(always)]
+    fn xor(self, other: Self) -> Self {
+        unsafe { _mm256_xor_si256(self, other) }
+    }
+
+    #[inline(always)]
+    fn and(self, other: Self) -> Self {
+        unsafe { _mm256_and_si256(self, other) }
+    }
+
+    #[inline(always)]
+    fn or(self, other: Self) -> Self {
+        unsafe { _mm256_or_si256(self, other) }
+    }
+
+    #[inline(always)]
+    fn rotate_left(self, bits: u32) -> Self {
+        unsafe {
+            _mm256_or_si256(
+                _mm256_sll_epi32(self, _mm_cvtsi32_si128(bits as i32)),
+                _mm256_srl_epi32(self, _mm_cvtsi32_si128(32 - bits as i32)),
+            )
+        }
+    }
+}
+
+impl Word for __m512i {
+    const LANES: usize = 16;
+
+    #[inline(always)]
+    fn splat(value: u32) -> Self {
+        unsafe { _mm512_set1_epi32(value as i32) }
+    }
+
+    #[inline(always)]
+    fn load(values: &[u32]) -> Self {
+        let values = &values[..Self::LANES];
+        unsafe { _mm512_loadu_si512(values.as_ptr().cast()) }
+    }
+
+    #[inline(always)]
+    fn store(self, values: &mut [u32]) {
+        let values = &mut values[..Self::LANES];
+        unsafe { _mm512_storeu_si512(values.as_mut_ptr().cast(), self) }
+    }
+
+    #[inline(always)]
+    fn add(self, other: Self) -> Self {
+        unsafe { _mm512_add_epi32(self, other) }
+    }
+
+    #[inline(always)]
+    fn xor(self, other: Self) -> Self {
+        unsafe { _mm512_xor_si512(self, other) }
+    }
+
+    #[inline(always)]
+    fn and(self, other: Self) -> Self {
+        unsafe { _mm512_and_si512(self, other) }
+    }
+
+    #[inline(always)]
+    fn or(self, other: Self) -> Self {
+        unsafe { _mm512_or_si512(self, other) }
+    }
+
+    #[inline(always)]
+    fn rotate_left(self, bits: u32) -> Self {
+        unsafe { _mm512_rolv_epi32(self, _mm512_set1_epi32(bits as i32)) }
+    }
+
+    // The round functions are a single instruction each, given their truth
+    // tables
+
+    #[inline(always)]
+    fn choose(b: Self, c: Self, d: Self) -> Self {
+        unsafe { _mm512_ternarylogic_epi32::<0xCA>(b, c, d) }
+    }
+
+    #[inline(always)]
+    fn parity(b: Self, c: Self, d: Self) -> Self {
+        unsafe { _mm512_ternarylogic_epi32::<0x96>(b, c, d) }
+    }
+
+    #[inline(always)]
+    fn majority(b: Self, c: Self, d: Self) -> Self {
+        unsafe { _mm512_ternarylogic_epi32::<0xE8>(b, c, d) }
+    }
+}
+
+#[target_feature(enable = "sse2")]
+pub(super) unsafe fn iterate_sse2(chains: &mut [Chain]) {
+    iterate_lanes::<__m128i>(chains);
+}
+
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn iterate_avx2(chains: &mut [Chain]) {
+    iterate_lanes::<__m256i>(chains);
+}
+
+#[target_feature(enable = "avx512f")]
+pub(super) unsafe fn iterate_avx512(chains: &mut [Chain]) {
+    iterate_lanes::<__m512i>(chains);
+}
+
+/// A SHA-1 state laid out for the SHA extensions: A to D from the highest
+/// lane down, and E in the highest lane of its own register with the
+/// others left zero.
+#[derive(Clone, Copy)]
+struct ShaState {
+    abcd: __m128i,
+    e: __m128i,
+}
+
+impl ShaState {
+    #[inline(always)]
+    unsafe fn load(words: &[u32; 5]) -> Self {
+        let [a, b, c, d, e] = words.map(|word| word as i32);
+        Self {
+            abcd: _mm_set_epi32(a, b, c, d),
+            e: _mm_set_epi32(e, 0, 0, 0),
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn store(self, words: &mut [u32; 5]) {
+        let mut abcd = [0u32; 4];
+        _mm_storeu_si128(abcd.as_mut_ptr().cast(), self.abcd);
+        *words = [
+            abcd[3],
+            abcd[2],
+            abcd[1],
+            abcd[0],
+            _mm_extract_epi32::<3>(self.e) as u32,
+        ];
+    }
+
+    #[inline(always)]
+    unsafe fn xor(self, other: Self) -> Self {
+        Self {
+            abcd: _mm_xor_si128(self.abcd, other.abcd),
+            e: _mm_xor_si128(self.e, other.e),
+        }
+    }
+}
+
+/// How many chains go through the SHA extensions side by side. Each
+/// compression waits on the one before, so one chain alone would leave the
+/// units idle much of the time, while more than two run out of registers.
+const SHA_NI_CHAINS: usize = 2;
+
+type Lanes = [__m128i; SHA_NI_CHAINS];
+
+/// Does the same to every chain, one instruction at a time, so that the
+/// instructions of different chains sit next to each other.
+macro_rules! each {
+    (|$n:ident| $body:expr) => {
+        std::array::from_fn::<_, SHA_NI_CHAINS, _>(|$n| $body)
+    };
+}
+
+macro_rules! rounds4 {
+    ($h0:expr, $h1:expr, $wk:expr, $i:literal) => {{
+        let e: Lanes = each!(|n| _mm_sha1nexte_epu32($h1[n], $wk[n]));
+        each!(|n| _mm_sha1rnds4_epu32::<$i>($h0[n], e[n]))
+    }};
+}
+
+macro_rules! schedule {
+    ($v0:expr, $v1:expr, $v2:expr, $v3:expr) => {{
+        let msg1: Lanes = each!(|n| _mm_sha1msg1_epu32($v0[n], $v1[n]));
+        let xor: Lanes = each!(|n| _mm_xor_si128(msg1[n], $v2[n]));
+        each!(|n| _mm_sha1msg2_epu32(xor[n], $v3[n]))
+    }};
+}
+
+macro_rules! schedule_rounds4 {
+    ($h0:ident, $h1:ident, $w0:expr, $w1:expr, $w2:expr, $w3:expr, $w4:expr, $i:literal) => {
+        $w4 = schedule!($w0, $w1, $w2, $w3);
+        $h1 = rounds4!($h0, $h1, $w4, $i);
+    };
+}
+
+/// [`hash20`](super::hash20) on the SHA extensions, for every chain.
+#[inline(always)]
+unsafe fn hash20(
+    state: &[ShaState; SHA_NI_CHAINS],
+    message: &[ShaState; SHA_NI_CHAINS],
+) -> [ShaState; SHA_NI_CHAINS] {
+    let abcd: Lanes = each!(|n| state[n].abcd);
+    let padding = _mm_set_epi32(0, 0x8000_0000u32 as i32, 0, 0);
+    let mut w0: Lanes = each!(|n| message[n].abcd);
+    let mut w1: Lanes = each!(|n| _mm_or_si128(message[n].e, padding));
+    let mut w2: Lanes = [_mm_setzero_si128(); SHA_NI_CHAINS];
+    let mut w3: Lanes = [_mm_set_epi32(0, 0, 0, (64 + 20) * 8); SHA_NI_CHAINS];
+    let mut w4: Lanes;
+
+    // Rounds 0 to 19
+    let wk: Lanes = each!(|n| _mm_add_epi32(state[n].e, w0[n]));
+    let mut h1: Lanes = each!(|n| _mm_sha1rnds4_epu32::<0>(abcd[n], wk[n]));
+    let mut h0 = rounds4!(h1, abcd, w1, 0);
+    h1 = rounds4!(h0, h1, w2, 0);
+    h0 = rounds4!(h1, h0, w3, 0);
+    schedule_rounds4!(h0, h1, w0, w1, w2, w3, w4, 0);
+
+    // Rounds 20 to 39
+    schedule_rounds4!(h1, h0, w1, w2, w3, w4, w0, 1);
+    schedule_rounds4!(h0, h1, w2, w3, w4, w0, w1, 1);
+    schedule_rounds4!(h1, h0, w3, w4, w0, w1, w2, 1);
+    schedule_rounds4!(h0, h1, w4, w0, w1, w2, w3, 1);
+    schedule_rounds4!(h1, h0, w0, w1, w2, w3, w4, 1);
+
+    // Rounds 40 to 59
+    schedule_rounds4!(h0, h1, w1, w2, w3, w4, w0, 2);
+    schedule_rounds4!(h1, h0, w2, w3, w4, w0, w1, 2);
+    schedule_rounds4!(h0, h1, w3, w4, w0, w1, w2, 2);
+    schedule_rounds4!(h1, h0, w4, w0, w1, w2, w3, 2);
+    schedule_rounds4!(h0, h1, w0, w1, w2, w3, w4, 2);
+
+    // Rounds 60 to 79
+    schedule_rounds4!(h1, h0, w1, w2, w3, w4, w0, 3);
+    schedule_rounds4!(h0, h1, w2, w3, w4, w0, w1, 3);
+    schedule_rounds4!(h1, h0, w3, w4, w0, w1, w2, 3);
+    schedule_rounds4!(h0, h1, w4, w0, w1, w2, w3, 3);
+    schedule_rounds4!(h1, h0, w0, w1, w2, w3, w4, 3);
+
+    each!(|n| ShaState {
+        abcd: _mm_add_epi32(abcd[n], h0[n]),
+        e: _mm_sha1nexte_epu32(h1[n], state[n].e),
+    })
+}
+
+#[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+pub(super) unsafe fn iterate_sha_ni(chains: &mut [Chain]) {
+    for group in chains.chunks_mut(SHA_NI_CHAINS) {
+        // Like the lanes of the other backends, a short group repeats its
+        // last chain
+        let gather = |word: fn(&Chain) -> &[u32; 5]| {
+            each!(|n| ShaState::load(word(&group[n.min(group.len() - 1)])))
+        };
+        let ipad = gather(|chain| &chain.ipad);
+        let opad = gather(|chain| &chain.opad);
+        let mut u = gather(|chain| &chain.u);
+        let mut t = gather(|chain| &chain.t);
+
+        for _ in 1..ITERATIONS {
+            u = hash20(&opad, &hash20(&ipad, &u));
+            t = each!(|n| t[n].xor(u[n]));
+        }
+
+        for (chain, t) in group.iter_mut().zip(t) {
+            t.store(&mut chain.t);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::{decode_candidate, format_passphrase, PmkidTarget};
+
+/// Every passphrase recovered so far, so that a target only has to be
+/// cracked once.
+///
+/// The file is compatible with hashcat's potfile for mode 22000: one
+/// `PMKID:MAC_AP:MAC_STA:ESSID:passphrase` line per target, with the ESSID
+/// and passphrase written as `$HEX[...]` when they aren't printable. The
+/// `PMKID*MAC_AP*MAC_STA*ESSID_HEX:passphrase` lines of mode 16800 and older
+/// hashcat versions are read as well. Lines for other hash types are left
+/// alone. New passphrases are appended, so several crackers can share a
+/// potfile.
+#[derive(Debug)]
+pub struct Potfile {
+    path: PathBuf,
+    /// Passphrases by the hash line of their target.
+    entries: Mutex<HashMap<String, Vec<u8>>>,
+    skipped: usize,
+}
+
+impl Potfile {
+    /// Reads the potfile at `path`. A missing file is an empty potfile, which
+    /// is created once something is added to it.
+    pub fn load(path: PathBuf) -> io::Result<Self> {
+        let mut entries = HashMap::new();
+        let mut skipped = 0;
+        match File::open(&path) {
+            Ok(file) => {
+                for line in BufReader::new(file).split(b'\n') {
+                    let line = line?;
+                    match parse_line(&line) {
+                        Some((target, passphrase)) => {
+                            entries.insert(target.to_hash_line(), passphrase);
+                        }
+                        None if line.iter().all(u8::is_ascii_whitespace) => {}
+                        None => skipped += 1,
+                    }
+                }
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+
+        Ok(Self {
+            path,
+            entries: Mutex::new(entries),
+            skipped,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// How many lines of the file weren't for a PMKID target when it was
+    /// loaded, e.g. those of other hash types.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// The passphrase recorded for `target`, if any.
+    pub fn get(&self, target: &PmkidTarget) -> Option<Vec<u8>> {
+        let entries = self.entries.lock().unwrap();
+        entries.get(&target.to_hash_line()).cloned()
+    }
+
+    /// Records `passphrase` for `target`, unless it is there already.
+    pub fn insert(&self, target: &PmkidTarget, passphrase: &[u8]) -> io::Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        let hash_line = target.to_hash_line();
+        if entries.get(&hash_line).map(Vec::as_slice) == Some(passphrase) {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(
+            file,
+            "{}:{}:{}:{}:{}",
+            hex::encode(target.pmkid),
+            hex::encode(target.mac_ap),
+            hex::encode(target.mac_sta),
+            format_essid(&target.essid),
+            format_passphrase(passphrase)
+        )?;
+
+        entries.insert(hash_line, passphrase.to_vec());
+        Ok(())
+    }
+}
+
+/// Writes an ESSID as text, unless it needs `$HEX[...]` to be read back,
+/// which includes ESSIDs with a `:` in them.
+fn format_essid(essid: &[u8]) -> String {
+    let text = format_passphrase(essid);
+    if text.contains(':') && !text.starts_with("$HEX[") {
+        return format!("$HEX[{}]", hex::encode(essid));
+    }
+    text
+}
+
+/// Splits a potfile line into its target and passphrase.
+///
+/// A 16800 hash line has no `:` in it, so the passphrase is everything after
+/// the first one. Otherwise the target takes up the first four fields, with
+/// the ESSID as text or `$HEX[...]`, and the passphrase is the rest.
+fn parse_line(line: &[u8]) -> Option<(PmkidTarget, Vec<u8>)> {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    let split = line.iter().position(|&byte| byte == b':')?;
+    if line[..split].contains(&b'*') {
+        let hash_line = std::str::from_utf8(&line[..split]).ok()?;
+        let target = PmkidTarget::from_hash_line(hash_line).ok()?;
+        return Some((target, decode_candidate(&line[split + 1..])));
+    }
+
+    let fields: Vec<&[u8]> = line.splitn(5, |&byte| byte == b':').collect();
+    let (pmkid, mac_ap, mac_sta, essid, passphrase) = match fields[..] {
+        [pmkid, mac_ap, mac_sta, essid, passphrase] => (pmkid, mac_ap, mac_sta, essid, passphrase),
+        _ => return None,
+    };
+    let hash_line = format!(
+        "{}*{}*{}*{}",
+        std::str::from_utf8(pmkid).ok()?,
+        std::str::from_utf8(mac_ap).ok()?,
+        std::str::from_utf8(mac_sta).ok()?,
+        hex::encode(decode_candidate(essid)),
+    );
+    let target = PmkidTarget::from_hash_line(&hash_line).ok()?;
+    Some((target, decode_candidate(passphrase)))
+}
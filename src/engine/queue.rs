@@ -0,0 +1,107 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::fmt;
+use std::io;
+
+use super::{Batch, CandidateSource, Position};
+
+/// Several candidate sources chained into one, tried in the order they were
+/// pushed.
+///
+/// Progress through the queue adds up the progress through each source, so a
+/// source that is skipped counts as done. Every [`Batch`] it hands out comes
+/// from a single source, whose index is in [`Position::source`].
+#[derive(Default)]
+pub struct WordlistQueue {
+    sources: Vec<Box<dyn CandidateSource>>,
+    /// Index of the source being read.
+    current: usize,
+    /// Sum of the totals of the sources before `current`.
+    done: u64,
+}
+
+impl fmt::Debug for WordlistQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WordlistQueue")
+            .field("sources", &self.sources.len())
+            .field("current", &self.current)
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+impl WordlistQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push<S: CandidateSource + 'static>(&mut self, source: S) {
+        self.sources.push(Box::new(source));
+    }
+
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// The total of each source, in queue order.
+    pub fn totals(&self) -> Vec<u64> {
+        self.sources.iter().map(|source| source.total()).collect()
+    }
+
+    /// Moves on to the `source`th source, counting the ones in between as
+    /// done.
+    fn advance_to(&mut self, source: usize) {
+        let source = source.min(self.sources.len());
+        while self.current < source {
+            self.done += self.sources[self.current].total();
+            self.current += 1;
+        }
+    }
+}
+
+impl CandidateSource for WordlistQueue {
+    fn total(&self) -> u64 {
+        self.sources.iter().map(|source| source.total()).sum()
+    }
+
+    fn progress(&self) -> u64 {
+        self.done
+            + self
+                .sources
+                .get(self.current)
+                .map_or(0, |source| source.progress())
+    }
+
+    fn next_batch(&mut self) -> io::Result<Option<Batch>> {
+        while let Some(source) = self.sources.get_mut(self.current) {
+            match source.next_batch()? {
+                Some(mut batch) => {
+                    batch.end.source = self.current;
+                    batch.progress += self.done;
+                    return Ok(Some(batch));
+                }
+                None => self.advance_to(self.current + 1),
+            }
+        }
+        Ok(None)
+    }
+
+    fn skip_to(&mut self, position: Position) -> io::Result<()> {
+        if position.source < self.current {
+            return Ok(());
+        }
+        self.advance_to(position.source);
+        match self.sources.get_mut(self.current) {
+            Some(source) => source.skip_to(Position {
+                source: 0,
+                offset: position.offset,
+            }),
+            None => Ok(()),
+        }
+    }
+}
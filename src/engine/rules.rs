@@ -0,0 +1,601 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use bstr::ByteSlice;
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::pairs::Pairs;
+use super::{Batch, CandidateSource, Position};
+
+/// Longest word a rule may produce, as in hashcat. Rules that would go past
+/// it reject the word instead.
+const MAX_WORD_LEN: usize = 256;
+
+/// A single rule function, e.g. `c`, `$1` or `sa@`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    /// `:`
+    Noop,
+    /// `l`
+    Lower,
+    /// `u`
+    Upper,
+    /// `c`
+    Capitalize,
+    /// `C`
+    InvertCapitalize,
+    /// `t`
+    ToggleAll,
+    /// `TN`
+    Toggle(usize),
+    /// `r`
+    Reverse,
+    /// `d`
+    Duplicate,
+    /// `pN`
+    DuplicateTimes(usize),
+    /// `f`
+    Reflect,
+    /// `{`
+    RotateLeft,
+    /// `}`
+    RotateRight,
+    /// `$X`
+    Append(u8),
+    /// `^X`
+    Prepend(u8),
+    /// `[`
+    DeleteFirst,
+    /// `]`
+    DeleteLast,
+    /// `DN`
+    DeleteAt(usize),
+    /// `xNM`
+    Extract(usize, usize),
+    /// `ONM`
+    Omit(usize, usize),
+    /// `iNX`
+    Insert(usize, u8),
+    /// `oNX`
+    Overwrite(usize, u8),
+    /// `'N`
+    Truncate(usize),
+    /// `sXY`
+    Replace(u8, u8),
+    /// `@X`
+    Purge(u8),
+    /// `zN`
+    DuplicateFirst(usize),
+    /// `ZN`
+    DuplicateLast(usize),
+    /// `q`
+    DuplicateAll,
+    /// `k`
+    SwapFront,
+    /// `K`
+    SwapBack,
+    /// `*NM`
+    Swap(usize, usize),
+    /// `LN`
+    ShiftLeft(usize),
+    /// `RN`
+    ShiftRight(usize),
+    /// `+N`
+    Increment(usize),
+    /// `-N`
+    Decrement(usize),
+    /// `.N`
+    ReplaceWithNext(usize),
+    /// `,N`
+    ReplaceWithPrevious(usize),
+    /// `yN`
+    DuplicateBlockFront(usize),
+    /// `YN`
+    DuplicateBlockBack(usize),
+    /// `E`
+    Title,
+    /// `eX`
+    TitleSeparator(u8),
+    /// `M`
+    Memorize,
+    /// `4`
+    AppendMemory,
+    /// `6`
+    PrependMemory,
+    /// `XNMI`
+    InsertMemory(usize, usize, usize),
+    /// `Q`
+    RejectMemory,
+    /// `<N`
+    RejectLonger(usize),
+    /// `>N`
+    RejectShorter(usize),
+    /// `_N`
+    RejectNotLength(usize),
+    /// `!X`
+    RejectContains(u8),
+    /// `/X`
+    RejectNotContains(u8),
+    /// `(X`
+    RejectNotFirst(u8),
+    /// `)X`
+    RejectNotLast(u8),
+    /// `=NX`
+    RejectNotAt(usize, u8),
+    /// `%NX`
+    RejectFewer(usize, u8),
+}
+
+/// Why a line of a rule file couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleError {
+    UnknownFunction(char),
+    MissingArgument(char),
+    InvalidPosition(char),
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFunction(function) => write!(f, "Unknown rule function '{}'", function),
+            Self::MissingArgument(function) => {
+                write!(f, "Missing argument for rule function '{}'", function)
+            }
+            Self::InvalidPosition(position) => write!(f, "Invalid position '{}'", position),
+        }
+    }
+}
+
+impl Error for RuleError {}
+
+/// Positions and counts are written 0-9, then A-Z for 10-35.
+fn parse_position(byte: u8) -> Result<usize, RuleError> {
+    match byte {
+        b'0'..=b'9' => Ok((byte - b'0') as usize),
+        b'A'..=b'Z' => Ok((byte - b'A') as usize + 10),
+        _ => Err(RuleError::InvalidPosition(byte as char)),
+    }
+}
+
+/// A line of hashcat/John-style rule functions, applied left to right.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule(Vec<Op>);
+
+impl Rule {
+    /// Parses a line of rule functions. It is taken as bytes, so arguments
+    /// that aren't ASCII are appended or matched as they are.
+    pub fn parse(line: impl AsRef<[u8]>) -> Result<Self, RuleError> {
+        let mut bytes = line.as_ref().iter().copied();
+        let mut ops = Vec::new();
+
+        while let Some(function) = bytes.next() {
+            let name = function as char;
+            let mut arg = || bytes.next().ok_or(RuleError::MissingArgument(name));
+            let op = match function {
+                // Spaces between functions are ignored, like hashcat does
+                b' ' => continue,
+                b':' => Op::Noop,
+                b'l' => Op::Lower,
+                b'u' => Op::Upper,
+                b'c' => Op::Capitalize,
+                b'C' => Op::InvertCapitalize,
+                b't' => Op::ToggleAll,
+                b'T' => Op::Toggle(parse_position(arg()?)?),
+                b'r' => Op::Reverse,
+                b'd' => Op::Duplicate,
+                b'p' => Op::DuplicateTimes(parse_position(arg()?)?),
+                b'f' => Op::Reflect,
+                b'{' => Op::RotateLeft,
+                b'}' => Op::RotateRight,
+                b'$' => Op::Append(arg()?),
+                b'^' => Op::Prepend(arg()?),
+                b'[' => Op::DeleteFirst,
+                b']' => Op::DeleteLast,
+                b'D' => Op::DeleteAt(parse_position(arg()?)?),
+                b'x' => Op::Extract(parse_position(arg()?)?, parse_position(arg()?)?),
+                b'O' => Op::Omit(parse_position(arg()?)?, parse_position(arg()?)?),
+                b'i' => Op::Insert(parse_position(arg()?)?, arg()?),
+                b'o' => Op::Overwrite(parse_position(arg()?)?, arg()?),
+                b'\'' => Op::Truncate(parse_position(arg()?)?),
+                b's' => Op::Replace(arg()?, arg()?),
+                b'@' => Op::Purge(arg()?),
+                b'z' => Op::DuplicateFirst(parse_position(arg()?)?),
+                b'Z' => Op::DuplicateLast(parse_position(arg()?)?),
+                b'q' => Op::DuplicateAll,
+                b'k' => Op::SwapFront,
+                b'K' => Op::SwapBack,
+                b'*' => Op::Swap(parse_position(arg()?)?, parse_position(arg()?)?),
+                b'L' => Op::ShiftLeft(parse_position(arg()?)?),
+                b'R' => Op::ShiftRight(parse_position(arg()?)?),
+                b'+' => Op::Increment(parse_position(arg()?)?),
+                b'-' => Op::Decrement(parse_position(arg()?)?),
+                b'.' => Op::ReplaceWithNext(parse_position(arg()?)?),
+                b',' => Op::ReplaceWithPrevious(parse_position(arg()?)?),
+                b'y' => Op::DuplicateBlockFront(parse_position(arg()?)?),
+                b'Y' => Op::DuplicateBlockBack(parse_position(arg()?)?),
+                b'E' => Op::Title,
+                b'e' => Op::TitleSeparator(arg()?),
+                b'M' => Op::Memorize,
+                b'4' => Op::AppendMemory,
+                b'6' => Op::PrependMemory,
+                b'X' => Op::InsertMemory(
+                    parse_position(arg()?)?,
+                    parse_position(arg()?)?,
+                    parse_position(arg()?)?,
+                ),
+                b'Q' => Op::RejectMemory,
+                b'<' => Op::RejectLonger(parse_position(arg()?)?),
+                b'>' => Op::RejectShorter(parse_position(arg()?)?),
+                b'_' => Op::RejectNotLength(parse_position(arg()?)?),
+                b'!' => Op::RejectContains(arg()?),
+                b'/' => Op::RejectNotContains(arg()?),
+                b'(' => Op::RejectNotFirst(arg()?),
+                b')' => Op::RejectNotLast(arg()?),
+                b'=' => Op::RejectNotAt(parse_position(arg()?)?, arg()?),
+                b'%' => Op::RejectFewer(parse_position(arg()?)?, arg()?),
+                _ => return Err(RuleError::UnknownFunction(name)),
+            };
+            ops.push(op);
+        }
+
+        Ok(Self(ops))
+    }
+
+    /// Mangles `word`, or returns `None` if the rule rejects it.
+    ///
+    /// Functions given positions past the end of the word leave it as it is.
+    pub fn apply(&self, word: &[u8]) -> Option<Vec<u8>> {
+        let mut word = word.to_vec();
+        let mut memory = Vec::new();
+
+        for op in &self.0 {
+            let len = word.len();
+            match *op {
+                Op::Noop => {}
+                Op::Lower => word.make_ascii_lowercase(),
+                Op::Upper => word.make_ascii_uppercase(),
+                Op::Capitalize => {
+                    word.make_ascii_lowercase();
+                    if let Some(first) = word.first_mut() {
+                        first.make_ascii_uppercase();
+                    }
+                }
+                Op::InvertCapitalize => {
+                    word.make_ascii_uppercase();
+                    if let Some(first) = word.first_mut() {
+                        first.make_ascii_lowercase();
+                    }
+                }
+                Op::ToggleAll => word.iter_mut().for_each(toggle_case),
+                Op::Toggle(n) => {
+                    if let Some(byte) = word.get_mut(n) {
+                        toggle_case(byte);
+                    }
+                }
+                Op::Reverse => word.reverse(),
+                Op::Duplicate => word.extend_from_within(..),
+                Op::DuplicateTimes(n) => {
+                    for _ in 0..n {
+                        word.extend_from_within(..len);
+                    }
+                }
+                Op::Reflect => {
+                    let reversed: Vec<u8> = word.iter().rev().copied().collect();
+                    word.extend(reversed);
+                }
+                Op::RotateLeft => {
+                    if len > 0 {
+                        word.rotate_left(1);
+                    }
+                }
+                Op::RotateRight => {
+                    if len > 0 {
+                        word.rotate_right(1);
+                    }
+                }
+                Op::Append(byte) => word.push(byte),
+                Op::Prepend(byte) => word.insert(0, byte),
+                Op::DeleteFirst => {
+                    if len > 0 {
+                        word.remove(0);
+                    }
+                }
+                Op::DeleteLast => {
+                    word.pop();
+                }
+                Op::DeleteAt(n) => {
+                    if n < len {
+                        word.remove(n);
+                    }
+                }
+                Op::Extract(n, m) => {
+                    if n + m <= len {
+                        word = word[n..n + m].to_vec();
+                    }
+                }
+                Op::Omit(n, m) => {
+                    if n + m <= len {
+                        word.drain(n..n + m);
+                    }
+                }
+                Op::Insert(n, byte) => {
+                    if n <= len {
+                        word.insert(n, byte);
+                    }
+                }
+                Op::Overwrite(n, byte) => {
+                    if let Some(old) = word.get_mut(n) {
+                        *old = byte;
+                    }
+                }
+                Op::Truncate(n) => word.truncate(n),
+                Op::Replace(from, to) => word
+                    .iter_mut()
+                    .filter(|byte| **byte == from)
+                    .for_each(|byte| *byte = to),
+                Op::Purge(byte) => word.retain(|&other| other != byte),
+                Op::DuplicateFirst(n) => {
+                    if let Some(&first) = word.first() {
+                        word.splice(0..0, vec![first; n]);
+                    }
+                }
+                Op::DuplicateLast(n) => {
+                    if let Some(&last) = word.last() {
+                        word.resize(len + n, last);
+                    }
+                }
+                Op::DuplicateAll => {
+                    word = word.iter().flat_map(|&byte| [byte, byte]).collect();
+                }
+                Op::SwapFront => {
+                    if len >= 2 {
+                        word.swap(0, 1);
+                    }
+                }
+                Op::SwapBack => {
+                    if len >= 2 {
+                        word.swap(len - 1, len - 2);
+                    }
+                }
+                Op::Swap(n, m) => {
+                    if n < len && m < len {
+                        word.swap(n, m);
+                    }
+                }
+                Op::ShiftLeft(n) => {
+                    if let Some(byte) = word.get_mut(n) {
+                        *byte <<= 1;
+                    }
+                }
+                Op::ShiftRight(n) => {
+                    if let Some(byte) = word.get_mut(n) {
+                        *byte >>= 1;
+                    }
+                }
+                Op::Increment(n) => {
+                    if let Some(byte) = word.get_mut(n) {
+                        *byte = byte.wrapping_add(1);
+                    }
+                }
+                Op::Decrement(n) => {
+                    if let Some(byte) = word.get_mut(n) {
+                        *byte = byte.wrapping_sub(1);
+                    }
+                }
+                Op::ReplaceWithNext(n) => {
+                    if n + 1 < len {
+                        word[n] = word[n + 1];
+                    }
+                }
+                Op::ReplaceWithPrevious(n) => {
+                    if n >= 1 && n < len {
+                        word[n] = word[n - 1];
+                    }
+                }
+                Op::DuplicateBlockFront(n) => {
+                    if n <= len {
+                        word.splice(0..0, word[..n].to_vec());
+                    }
+                }
+                Op::DuplicateBlockBack(n) => {
+                    if n <= len {
+                        word.extend_from_within(len - n..);
+                    }
+                }
+                Op::Title => title_case(&mut word, b' '),
+                Op::TitleSeparator(separator) => title_case(&mut word, separator),
+                Op::Memorize => memory = word.clone(),
+                Op::AppendMemory => word.extend_from_slice(&memory),
+                Op::PrependMemory => {
+                    word.splice(0..0, memory.iter().copied());
+                }
+                Op::InsertMemory(n, m, i) => {
+                    if n + m <= memory.len() && i <= len {
+                        word.splice(i..i, memory[n..n + m].iter().copied());
+                    }
+                }
+                Op::RejectMemory => {
+                    if word == memory {
+                        return None;
+                    }
+                }
+                Op::RejectLonger(n) => {
+                    if len > n {
+                        return None;
+                    }
+                }
+                Op::RejectShorter(n) => {
+                    if len < n {
+                        return None;
+                    }
+                }
+                Op::RejectNotLength(n) => {
+                    if len != n {
+                        return None;
+                    }
+                }
+                Op::RejectContains(byte) => {
+                    if word.contains(&byte) {
+                        return None;
+                    }
+                }
+                Op::RejectNotContains(byte) => {
+                    if !word.contains(&byte) {
+                        return None;
+                    }
+                }
+                Op::RejectNotFirst(byte) => {
+                    if word.first() != Some(&byte) {
+                        return None;
+                    }
+                }
+                Op::RejectNotLast(byte) => {
+                    if word.last() != Some(&byte) {
+                        return None;
+                    }
+                }
+                Op::RejectNotAt(n, byte) => {
+                    if word.get(n) != Some(&byte) {
+                        return None;
+                    }
+                }
+                Op::RejectFewer(n, byte) => {
+                    if word.iter().filter(|&&other| other == byte).count() < n {
+                        return None;
+                    }
+                }
+            }
+
+            if word.len() > MAX_WORD_LEN {
+                return None;
+            }
+        }
+
+        Some(word)
+    }
+}
+
+fn toggle_case(byte: &mut u8) {
+    if byte.is_ascii_lowercase() {
+        byte.make_ascii_uppercase();
+    } else if byte.is_ascii_uppercase() {
+        byte.make_ascii_lowercase();
+    }
+}
+
+/// Lowercases `word`, then uppercases its first letter and every letter
+/// following `separator`.
+fn title_case(word: &mut [u8], separator: u8) {
+    let mut start = true;
+    for byte in word {
+        if start {
+            byte.make_ascii_uppercase();
+        } else {
+            byte.make_ascii_lowercase();
+        }
+        start = *byte == separator;
+    }
+}
+
+/// The rules of a `.rule` file, one per line.
+///
+/// Each candidate is tried once per rule, so a file of only `:` tries the
+/// wordlist as it is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+    /// Lines that couldn't be parsed, by line number, counting from 1.
+    invalid: Vec<(usize, RuleError)>,
+}
+
+impl RuleSet {
+    /// Parses a rule file. Empty lines and `#` comments are ignored, and
+    /// invalid lines are set aside in [`invalid`](Self::invalid), the way
+    /// hashcat skips them.
+    pub fn parse(text: impl AsRef<[u8]>) -> Self {
+        let mut set = Self::default();
+        for (index, line) in text.as_ref().lines().enumerate() {
+            if line.is_empty() || line.starts_with(b"#") {
+                continue;
+            }
+            match Rule::parse(line) {
+                Ok(rule) => set.rules.push(rule),
+                Err(err) => set.invalid.push((index + 1, err)),
+            }
+        }
+        set
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Ok(Self::parse(fs::read(path)?))
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn invalid(&self) -> &[(usize, RuleError)] {
+        &self.invalid
+    }
+
+    /// Every candidate the rules make out of `word`, in rule order.
+    pub fn apply<'a>(&'a self, word: &'a [u8]) -> impl Iterator<Item = Vec<u8>> + 'a {
+        self.rules.iter().filter_map(move |rule| rule.apply(word))
+    }
+}
+
+/// Runs every candidate of a source through a [`RuleSet`].
+///
+/// Every rule is applied to a word before moving on to the next, a batch at
+/// a time, so that big rule files don't make for huge batches. Like for a
+/// [`Hybrid`](super::Hybrid), progress and positions count the pairs of a
+/// word and a rule, out of the number of words times the number of rules,
+/// and a checkpoint can be resumed from partway through the rules of a word.
+#[derive(Debug)]
+pub struct Mangled<S> {
+    pairs: Pairs<S>,
+    rules: Arc<RuleSet>,
+}
+
+impl<S: CandidateSource> Mangled<S> {
+    /// Mangles the `words` words of `source`, e.g. as counted by
+    /// [`Wordlist::count_lines`](super::Wordlist::count_lines). The count
+    /// only matters for progress, so it can be 0 when that isn't followed,
+    /// as with [`collect_candidates`](super::collect_candidates).
+    pub fn new(source: S, rules: Arc<RuleSet>, words: u64) -> Self {
+        Self {
+            pairs: Pairs::new(source, rules.len() as u64, words),
+            rules,
+        }
+    }
+}
+
+impl<S: CandidateSource> CandidateSource for Mangled<S> {
+    fn total(&self) -> u64 {
+        self.pairs.total()
+    }
+
+    fn progress(&self) -> u64 {
+        self.pairs.progress()
+    }
+
+    fn next_batch(&mut self) -> io::Result<Option<Batch>> {
+        let rules = &self.rules.rules;
+        self.pairs
+            .next_batch(|word, index| rules[index as usize].apply(word))
+    }
+
+    fn skip_to(&mut self, position: Position) -> io::Result<()> {
+        self.pairs.skip_to(position)
+    }
+}
@@ -0,0 +1,171 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How often the [`Status`] of a running [`Cracker`](super::Cracker) is
+/// worth polling.
+pub const STATUS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How much each new reading counts towards the smoothed rates, against all
+/// the ones before it.
+const SMOOTHING: f64 = 0.3;
+
+/// A snapshot of a running [`Cracker`](super::Cracker), taken through its
+/// [`StatusHandle`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Status {
+    /// Candidates tried so far in this run.
+    pub tried: u64,
+    /// Units of the source done so far, out of `total`. See
+    /// [`CandidateSource::total`](super::CandidateSource::total).
+    pub done: u64,
+    pub total: u64,
+    /// The source of a [`WordlistQueue`](super::WordlistQueue) the workers
+    /// are on.
+    pub source: usize,
+    pub elapsed: Duration,
+    /// Candidates tried per second, smoothed over the last few snapshots.
+    pub rate: f64,
+    /// Roughly how long until the whole source is done, once there is a
+    /// rate to go by.
+    pub eta: Option<Duration>,
+    /// One of the candidates tried lately.
+    pub candidate: Option<Vec<u8>>,
+    /// The passphrase of each of [`Cracker::targets`](super::Cracker::targets)
+    /// found so far, including those already in the potfile.
+    pub found: Vec<Option<Vec<u8>>>,
+}
+
+/// Shared counters the workers of a [`Cracker`](super::Cracker) add to as
+/// they go, so that its [`Status`] can be polled from another thread.
+///
+/// Like [`CancelToken`](super::CancelToken), clones refer to the same
+/// counters. Workers add whole chunks of candidates at once, so keeping
+/// count costs next to nothing however fast candidates are tried.
+#[derive(Debug, Clone, Default)]
+pub struct StatusHandle(Arc<StatusState>);
+
+#[derive(Debug, Default)]
+struct StatusState {
+    tried: AtomicU64,
+    /// Only ever moves forward, as workers finish out of order.
+    done: AtomicU64,
+    source: AtomicUsize,
+    candidate: Mutex<Option<Vec<u8>>>,
+    found: Mutex<Vec<Option<Vec<u8>>>>,
+    /// `None` until a run starts.
+    rates: Mutex<Option<Rates>>,
+}
+
+/// What the smoothed rates are worked out from.
+#[derive(Debug)]
+struct Rates {
+    total: u64,
+    started: Instant,
+    /// When the rates were last updated, and the counters at the time.
+    last: (Instant, u64, u64),
+    /// Candidates per second, and units of the source per second.
+    rates: Option<(f64, f64)>,
+}
+
+impl StatusHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The status of the current run, or of the last one once it is over.
+    pub fn snapshot(&self) -> Status {
+        let state = &self.0;
+        let now = Instant::now();
+        let tried = state.tried.load(Ordering::Relaxed);
+        let done = state.done.load(Ordering::Relaxed);
+
+        let mut rates = state.rates.lock().unwrap();
+        let rates = match rates.as_mut() {
+            Some(rates) => rates,
+            None => return Status::default(),
+        };
+
+        // Too short an interval says little about the rate, as with a
+        // snapshot taken right at the start
+        let (last_time, last_tried, last_done) = rates.last;
+        let interval = now.duration_since(last_time);
+        if interval >= STATUS_INTERVAL / 2 {
+            let secs = interval.as_secs_f64();
+            let rate = tried.saturating_sub(last_tried) as f64 / secs;
+            let unit_rate = done.saturating_sub(last_done) as f64 / secs;
+            rates.rates = Some(match rates.rates {
+                Some((old_rate, old_unit_rate)) => (
+                    old_rate + SMOOTHING * (rate - old_rate),
+                    old_unit_rate + SMOOTHING * (unit_rate - old_unit_rate),
+                ),
+                None => (rate, unit_rate),
+            });
+            rates.last = (now, tried, done);
+        }
+
+        let (rate, unit_rate) = rates.rates.unwrap_or_default();
+        Status {
+            tried,
+            done,
+            total: rates.total,
+            source: state.source.load(Ordering::Relaxed),
+            elapsed: now.duration_since(rates.started),
+            rate,
+            eta: (unit_rate > 0.0).then(|| {
+                Duration::from_secs_f64(rates.total.saturating_sub(done) as f64 / unit_rate)
+            }),
+            candidate: state.candidate.lock().unwrap().clone(),
+            found: state.found.lock().unwrap().clone(),
+        }
+    }
+
+    /// Starts over for a run beginning `done` units into a source of
+    /// `total`, with the passphrases in `found` known from the start.
+    pub(crate) fn start(&self, done: u64, total: u64, found: Vec<Option<Vec<u8>>>) {
+        let state = &self.0;
+        let mut rates = state.rates.lock().unwrap();
+        state.tried.store(0, Ordering::Relaxed);
+        state.done.store(done, Ordering::Relaxed);
+        state.source.store(0, Ordering::Relaxed);
+        state.candidate.lock().unwrap().take();
+        *state.found.lock().unwrap() = found;
+
+        let now = Instant::now();
+        *rates = Some(Rates {
+            total,
+            started: now,
+            last: (now, 0, done),
+            rates: None,
+        });
+    }
+
+    pub(crate) fn set_source(&self, source: usize) {
+        self.0.source.store(source, Ordering::Relaxed);
+    }
+
+    /// Adds `tried` candidates, the last of which was `last`, and moves the
+    /// progress up to `done`.
+    pub(crate) fn add(&self, tried: u64, done: u64, last: &[u8]) {
+        let state = &self.0;
+        state.tried.fetch_add(tried, Ordering::Relaxed);
+        self.advance(done);
+        // Another worker updating it will do just as well
+        if let Ok(mut candidate) = state.candidate.try_lock() {
+            *candidate = Some(last.to_vec());
+        }
+    }
+
+    /// Records `passphrase` for the target at `target`.
+    pub(crate) fn set_found(&self, target: usize, passphrase: &[u8]) {
+        self.0.found.lock().unwrap()[target] = Some(passphrase.to_vec());
+    }
+
+    /// Moves the progress up to `done`, if it isn't past it already.
+    pub(crate) fn advance(&self, done: u64) {
+        self.0.done.fetch_max(done, Ordering::Relaxed);
+    }
+}
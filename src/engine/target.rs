@@ -0,0 +1,256 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashSet;
+use std::fmt;
+use std::io::Read;
+
+/// A single PMKID captured from an access point, along with everything
+/// needed to recompute it from a passphrase.
+///
+/// PMKID = HMAC-SHA1-128(PMK, "PMK Name" | MAC_AP | MAC_STA)
+///
+/// Serializes as its [hash line](Self::to_hash_line).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct PmkidTarget {
+    pub essid: Vec<u8>,
+    pub mac_ap: [u8; 6],
+    pub mac_sta: [u8; 6],
+    pub pmkid: [u8; 16],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetError {
+    EmptyEssid,
+    InvalidEssid(String),
+    InvalidMac(String),
+    InvalidPmkid(String),
+    InvalidHashLine(String),
+    /// A `WPA*` record of a type other than a PMKID, e.g. `02` for an EAPOL
+    /// handshake.
+    UnsupportedRecord(String),
+}
+
+impl fmt::Display for TargetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyEssid => write!(f, "ESSID must not be empty"),
+            Self::InvalidEssid(essid) => write!(f, "\"{}\" is not a valid hex ESSID", essid),
+            Self::InvalidMac(mac) => write!(f, "\"{}\" is not a valid MAC address", mac),
+            Self::InvalidPmkid(pmkid) => write!(f, "\"{}\" is not a valid PMKID", pmkid),
+            Self::InvalidHashLine(line) => write!(
+                f,
+                "\"{}\" is not a WPA*01 or PMKID*MAC_AP*MAC_STA*ESSID_HEX hash line",
+                line
+            ),
+            Self::UnsupportedRecord(kind) => write!(
+                f,
+                "WPA*{} records can't be cracked, only PMKIDs (WPA*01)",
+                kind
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TargetError {}
+
+impl PmkidTarget {
+    /// Builds a target from the textual form used by the network page and
+    /// network JSON files.
+    ///
+    /// MAC addresses may use `:`, `-` or `.` as separators. `pmkid` may also
+    /// be a whole [hash line](Self::from_hash_line), whose ESSID and MAC
+    /// addresses are used instead of the others.
+    pub fn from_strings(
+        essid: &str,
+        bssid: &str,
+        sta_mac: &str,
+        pmkid: &str,
+    ) -> Result<Self, TargetError> {
+        if pmkid.contains('*') {
+            return Self::from_hash_line(pmkid);
+        }
+        if essid.is_empty() {
+            return Err(TargetError::EmptyEssid);
+        }
+
+        Ok(Self {
+            essid: essid.as_bytes().to_owned(),
+            mac_ap: parse_mac(bssid)?,
+            mac_sta: parse_mac(sta_mac)?,
+            pmkid: parse_pmkid(pmkid)?,
+        })
+    }
+
+    /// Parses a hash line as written by hcxpcapngtool for hashcat, either a
+    /// `WPA*01*PMKID*MAC_AP*MAC_STA*ESSID_HEX***` line of mode 22000 or
+    /// 22001, or a `PMKID*MAC_AP*MAC_STA*ESSID_HEX` line of mode 16800.
+    ///
+    /// Only PMKID records can be cracked, so `WPA*02` EAPOL records are an
+    /// [`UnsupportedRecord`](TargetError::UnsupportedRecord).
+    pub fn from_hash_line(line: &str) -> Result<Self, TargetError> {
+        let line = line.trim();
+        let invalid = || TargetError::InvalidHashLine(line.to_string());
+        let fields: Vec<&str> = line.split('*').collect();
+        let (pmkid, mac_ap, mac_sta, essid) = match fields[..] {
+            // WPA*TYPE*HASH*MAC_AP*MAC_STA*ESSID*ANONCE*EAPOL*MESSAGEPAIR,
+            // the last three of which can be left out
+            ["WPA", kind, hash, mac_ap, mac_sta, essid, ref rest @ ..] if rest.len() <= 3 => {
+                match kind {
+                    "01" => (),
+                    "02" => return Err(TargetError::UnsupportedRecord(kind.to_string())),
+                    _ => return Err(invalid()),
+                }
+                // A PMKID comes without a nonce or an EAPOL frame
+                if rest.iter().take(2).any(|field| !field.is_empty()) {
+                    return Err(invalid());
+                }
+                (hash, mac_ap, mac_sta, essid)
+            }
+            [pmkid, mac_ap, mac_sta, essid] => (pmkid, mac_ap, mac_sta, essid),
+            _ => return Err(invalid()),
+        };
+
+        let essid = hex::decode(essid).map_err(|_| TargetError::InvalidEssid(essid.to_string()))?;
+        if essid.is_empty() {
+            return Err(TargetError::EmptyEssid);
+        }
+
+        Ok(Self {
+            essid,
+            mac_ap: parse_mac(mac_ap)?,
+            mac_sta: parse_mac(mac_sta)?,
+            pmkid: parse_pmkid(pmkid)?,
+        })
+    }
+
+    /// Formats the target as a `PMKID*MAC_AP*MAC_STA*ESSID_HEX` hash line,
+    /// the inverse of [`from_hash_line`](Self::from_hash_line).
+    pub fn to_hash_line(&self) -> String {
+        format!(
+            "{}*{}*{}*{}",
+            hex::encode(self.pmkid),
+            hex::encode(self.mac_ap),
+            hex::encode(self.mac_sta),
+            hex::encode(&self.essid)
+        )
+    }
+
+    /// The message the PMKID is an HMAC of: `"PMK Name" | MAC_AP | MAC_STA`.
+    pub fn message(&self) -> [u8; 20] {
+        let mut message = [0; 20];
+        message[..8].copy_from_slice(b"PMK Name");
+        message[8..14].copy_from_slice(&self.mac_ap);
+        message[14..].copy_from_slice(&self.mac_sta);
+        message
+    }
+}
+
+impl From<PmkidTarget> for String {
+    fn from(target: PmkidTarget) -> Self {
+        target.to_hash_line()
+    }
+}
+
+impl TryFrom<String> for PmkidTarget {
+    type Error = TargetError;
+
+    fn try_from(line: String) -> Result<Self, Self::Error> {
+        Self::from_hash_line(&line)
+    }
+}
+
+/// A line of a hash file that isn't a valid hash line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashLineError {
+    /// Starting from 1.
+    pub line: usize,
+    pub error: TargetError,
+}
+
+impl fmt::Display for HashLineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+impl std::error::Error for HashLineError {}
+
+/// Parses every hash line of a hash file, e.g. a `.22000` file from
+/// hcxpcapngtool, as a separate target. See
+/// [`PmkidTarget::from_hash_line`].
+///
+/// Blank lines are skipped, and so are targets that already came up. The
+/// lines that aren't valid are returned alongside the targets, so that the
+/// others can still be cracked.
+pub fn read_hash_lines(text: &str) -> (Vec<PmkidTarget>, Vec<HashLineError>) {
+    let mut targets = Vec::new();
+    let mut seen = HashSet::new();
+    let mut errors = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match PmkidTarget::from_hash_line(line) {
+            Ok(target) => {
+                if seen.insert(target.clone()) {
+                    targets.push(target);
+                }
+            }
+            Err(error) => errors.push(HashLineError {
+                line: index + 1,
+                error,
+            }),
+        }
+    }
+    (targets, errors)
+}
+
+/// Writes `targets` out as a hash file of mode 16800, one
+/// [hash line](PmkidTarget::to_hash_line) each, for [`read_hash_lines`] or
+/// hashcat to read back.
+pub fn format_hash_lines(targets: &[PmkidTarget]) -> String {
+    targets
+        .iter()
+        .map(|target| target.to_hash_line() + "\n")
+        .collect()
+}
+
+/// The network description stored in Ramsh's JSON network files.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NetworkParams {
+    pub essid: String,
+    pub bssid: String,
+    pub sta_mac: String,
+    pub pmkid: String,
+}
+
+impl NetworkParams {
+    pub fn from_reader<R: Read>(reader: R) -> serde_json::Result<Self> {
+        serde_json::from_reader(reader)
+    }
+
+    pub fn to_target(&self) -> Result<PmkidTarget, TargetError> {
+        PmkidTarget::from_strings(&self.essid, &self.bssid, &self.sta_mac, &self.pmkid)
+    }
+}
+
+fn parse_pmkid(pmkid: &str) -> Result<[u8; 16], TargetError> {
+    hex::decode(pmkid.trim())
+        .ok()
+        .and_then(|bytes| <[u8; 16]>::try_from(bytes.as_slice()).ok())
+        .ok_or_else(|| TargetError::InvalidPmkid(pmkid.to_string()))
+}
+
+/// Parses a MAC address written with or without `:`, `-` or `.` separators.
+fn parse_mac(mac: &str) -> Result<[u8; 6], TargetError> {
+    let stripped = mac.trim().to_lowercase().replace([':', '-', '.'], "");
+
+    hex::decode(&stripped)
+        .ok()
+        .and_then(|bytes| <[u8; 6]>::try_from(bytes.as_slice()).ok())
+        .ok_or_else(|| TargetError::InvalidMac(mac.to_string()))
+}
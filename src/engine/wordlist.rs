@@ -0,0 +1,360 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use bstr::ByteSlice;
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use super::CancelToken;
+
+/// The most candidates handed out in one [`Batch`].
+const BATCH_LINES: usize = 4096;
+
+/// The most bytes read into one [`Batch`], so that lists of very long lines
+/// don't blow up memory either.
+const BATCH_BYTES: usize = 1024 * 1024;
+
+/// The longest line read from a wordlist, newline included. Longer lines are
+/// skipped without being held in memory, as hashcat does; no passphrase
+/// needs that much, even in `$HEX[...]` notation.
+pub const MAX_LINE_LEN: usize = 256;
+
+/// A place in a [`CandidateSource`] that can be resumed from.
+///
+/// `source` indexes into a [`WordlistQueue`](super::WordlistQueue), and is
+/// always 0 for sources that stand alone. For wordlists, `offset` is a byte
+/// offset into the decompressed list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Position {
+    #[serde(default)]
+    pub source: usize,
+    pub offset: u64,
+}
+
+impl Position {
+    /// The very beginning of the `source`th source.
+    pub fn start_of(source: usize) -> Self {
+        Self { source, offset: 0 }
+    }
+}
+
+/// A run of consecutive candidates from a [`CandidateSource`].
+///
+/// Candidates are raw bytes, they don't have to be valid UTF-8. A batch never
+/// spans more than one source of a queue.
+#[derive(Debug, Default)]
+pub struct Batch {
+    pub candidates: Vec<Vec<u8>>,
+    /// Position in the source right after the last candidate of the batch,
+    /// as understood by [`CandidateSource::skip_to`].
+    pub end: Position,
+    /// Progress through the source right after the batch, out of
+    /// [`CandidateSource::total`].
+    pub progress: u64,
+}
+
+/// Hands passphrase candidates to the [`Cracker`](super::Cracker) in batches.
+///
+/// Sources have two ways of measuring how far along they are: a position
+/// they can skip back to when resuming, and progress in whatever unit suits
+/// them best. For a [`Wordlist`], both are bytes, but progress through a
+/// compressed list counts the compressed bytes.
+pub trait CandidateSource: Send {
+    /// Size of the source, in the same unit as [`Batch::progress`].
+    fn total(&self) -> u64;
+
+    /// Progress through the source so far.
+    fn progress(&self) -> u64;
+
+    /// The next batch of candidates, or `None` once the source is exhausted.
+    fn next_batch(&mut self) -> io::Result<Option<Batch>>;
+
+    /// Moves past everything before `position`, to resume from a checkpoint
+    /// or to skip the rest of a source.
+    fn skip_to(&mut self, position: Position) -> io::Result<()>;
+}
+
+/// Compression formats [`Wordlist::open`] can see through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    /// Recognizes a format from the first bytes of a file.
+    pub fn detect(magic: &[u8]) -> Option<Self> {
+        if magic.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::Gzip)
+        } else if magic.starts_with(b"BZh") {
+            Some(Self::Bzip2)
+        } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Self::Xz)
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Self::Zstd)
+        } else {
+            None
+        }
+    }
+
+    fn decoder<R: BufRead + Send + 'static>(self, reader: R) -> io::Result<Box<dyn Read + Send>> {
+        Ok(match self {
+            Self::Gzip => Box::new(flate2::bufread::MultiGzDecoder::new(reader)),
+            Self::Bzip2 => Box::new(bzip2::bufread::MultiBzDecoder::new(reader)),
+            Self::Xz => Box::new(xz2::bufread::XzDecoder::new_multi_decoder(reader)),
+            Self::Zstd => Box::new(zstd::stream::read::Decoder::with_buffer(reader)?),
+        })
+    }
+}
+
+/// Counts the bytes read through it, for progress through compressed files.
+struct CountingReader<R> {
+    inner: R,
+    count: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+/// A wordlist streamed from a reader, one candidate per line.
+///
+/// Only a batch at a time is held in memory, so lists of any size can be
+/// used. Lines may end in `\n` or `\r\n`, are taken byte for byte and may
+/// use hashcat's `$HEX[...]` notation.
+///
+/// Wordlists opened from a file may be compressed with any of the
+/// [`Compression`] formats, and are decompressed on the fly.
+pub struct Wordlist {
+    reader: Box<dyn BufRead + Send>,
+    /// Decompressed bytes consumed.
+    position: u64,
+    /// Compressed bytes read, if the list is compressed.
+    compressed_read: Option<Arc<AtomicU64>>,
+    total: u64,
+}
+
+impl fmt::Debug for Wordlist {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Wordlist")
+            .field("position", &self.position)
+            .field("compressed", &self.compressed_read.is_some())
+            .field("total", &self.total)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Wordlist {
+    /// Streams `reader`, which holds `total` bytes.
+    pub fn from_reader<R: BufRead + Send + 'static>(reader: R, total: u64) -> Self {
+        Self {
+            reader: Box::new(reader),
+            position: 0,
+            compressed_read: None,
+            total,
+        }
+    }
+
+    /// Opens a wordlist file, decompressing it if its first bytes say it is
+    /// compressed.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let total = file.metadata()?.len();
+
+        let count = Arc::new(AtomicU64::new(0));
+        let mut reader = BufReader::new(CountingReader {
+            inner: file,
+            count: count.clone(),
+        });
+
+        match Compression::detect(reader.fill_buf()?) {
+            None => Ok(Self::from_reader(reader, total)),
+            Some(compression) => {
+                let decoder = compression.decoder(reader)?;
+                Ok(Self {
+                    reader: Box::new(BufReader::new(decoder)),
+                    position: 0,
+                    compressed_read: Some(count),
+                    total,
+                })
+            }
+        }
+    }
+
+    pub fn from_text(text: String) -> Self {
+        let total = text.len() as u64;
+        Self::from_reader(Cursor::new(text.into_bytes()), total)
+    }
+
+    /// Reads through the rest of the list and counts the lines
+    /// [`next_batch`](CandidateSource::next_batch) would hand out, leaving out
+    /// those longer than [`MAX_LINE_LEN`].
+    pub fn count_lines(&mut self) -> io::Result<u64> {
+        self.count_lines_until(&CancelToken::new())
+    }
+
+    /// Like [`count_lines`](Self::count_lines), but gives up with an
+    /// [`Interrupted`](io::ErrorKind::Interrupted) error once `cancel` is
+    /// cancelled.
+    pub fn count_lines_until(&mut self, cancel: &CancelToken) -> io::Result<u64> {
+        let mut lines = 0;
+        let mut line_len = 0;
+        loop {
+            if cancel.is_cancelled() {
+                return Err(io::Error::new(
+                    io::ErrorKind::Interrupted,
+                    "Counting lines was cancelled",
+                ));
+            }
+            let buf = self.reader.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+            for piece in buf.split_inclusive(|&byte| byte == b'\n') {
+                line_len += piece.len();
+                if piece.ends_with(b"\n") {
+                    if line_len <= MAX_LINE_LEN {
+                        lines += 1;
+                    }
+                    line_len = 0;
+                }
+            }
+            let read = buf.len();
+            self.reader.consume(read);
+            self.position += read as u64;
+        }
+        // A last line without a newline still counts
+        if line_len > 0 && line_len <= MAX_LINE_LEN {
+            lines += 1;
+        }
+        Ok(lines)
+    }
+
+    /// Reads past the rest of the current line, returning how many bytes
+    /// that took.
+    fn skip_line(&mut self) -> io::Result<usize> {
+        let mut skipped = 0;
+        loop {
+            let buf = self.reader.fill_buf()?;
+            if buf.is_empty() {
+                return Ok(skipped);
+            }
+            let (read, done) = match buf.find_byte(b'\n') {
+                Some(newline) => (newline + 1, true),
+                None => (buf.len(), false),
+            };
+            self.reader.consume(read);
+            skipped += read;
+            if done {
+                return Ok(skipped);
+            }
+        }
+    }
+}
+
+impl CandidateSource for Wordlist {
+    fn total(&self) -> u64 {
+        self.total
+    }
+
+    fn progress(&self) -> u64 {
+        match &self.compressed_read {
+            Some(count) => count.load(Ordering::Relaxed),
+            None => self.position,
+        }
+    }
+
+    fn next_batch(&mut self) -> io::Result<Option<Batch>> {
+        let mut candidates = Vec::new();
+        let mut batch_bytes = 0;
+        let mut line = Vec::new();
+
+        while candidates.len() < BATCH_LINES && batch_bytes < BATCH_BYTES {
+            line.clear();
+            let read = (&mut self.reader)
+                .take(MAX_LINE_LEN as u64)
+                .read_until(b'\n', &mut line)?;
+            if read == 0 {
+                break;
+            }
+            self.position += read as u64;
+            if read == MAX_LINE_LEN && !line.ends_with(b"\n") {
+                // Unless the list ends right here, the line is too long
+                let skipped = self.skip_line()?;
+                self.position += skipped as u64;
+                if skipped > 0 {
+                    continue;
+                }
+            }
+            batch_bytes += read;
+
+            if line.ends_with(b"\n") {
+                line.pop();
+                if line.ends_with(b"\r") {
+                    line.pop();
+                }
+            }
+            candidates.push(decode_candidate(&line));
+        }
+
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Batch {
+            candidates,
+            end: Position {
+                source: 0,
+                offset: self.position,
+            },
+            progress: self.progress(),
+        }))
+    }
+
+    fn skip_to(&mut self, position: Position) -> io::Result<()> {
+        // A standalone list is the only source there is
+        if position.source > 0 {
+            self.reader = Box::new(io::empty());
+            return Ok(());
+        }
+
+        let skip = position.offset.saturating_sub(self.position);
+        let skipped = io::copy(&mut self.reader.by_ref().take(skip), &mut io::sink())?;
+        self.position += skipped;
+        Ok(())
+    }
+}
+
+/// Decodes a wordlist line written in hashcat's `$HEX[...]` notation, which
+/// lets lists carry passphrases containing newlines or other awkward bytes.
+///
+/// Lines that don't use the notation, or whose hex is invalid, are taken
+/// literally.
+pub fn decode_candidate(line: &[u8]) -> Vec<u8> {
+    line.strip_prefix(b"$HEX[")
+        .and_then(|rest| rest.strip_suffix(b"]"))
+        .and_then(|hex| hex::decode(hex).ok())
+        .unwrap_or_else(|| line.to_vec())
+}
+
+/// Renders a passphrase for display. Anything that isn't printable UTF-8,
+/// or would itself read as `$HEX[...]`, is shown in `$HEX[...]` notation.
+pub fn format_passphrase(passphrase: &[u8]) -> String {
+    match passphrase.to_str() {
+        Ok(text) if !text.chars().any(char::is_control) && !text.starts_with("$HEX[") => {
+            text.to_string()
+        }
+        _ => format!("$HEX[{}]", hex::encode(passphrase)),
+    }
+}
@@ -0,0 +1,7 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The GTK-free parts of Ramsh, shared between the application and anything
+//! else that wants to drive the cracking engine without a display server.
+
+pub mod engine;
@@ -1,27 +1,166 @@
 // Copyright 2022 Mufeed Ali
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use adw::prelude::*;
 use adw::subclass::prelude::*;
 use glib::clone;
-use gtk::prelude::*;
-use gtk::subclass::prelude::*;
 use gtk::{gio, glib};
 
-use bstr::ByteSlice;
-use fastpbkdf2::pbkdf2_hmac_sha1 as pbkdf2_hmac;
-use hex;
-use rayon::prelude::*;
-use rustc_serialize::hex::ToHex;
-
-use std::fs::{read_to_string, File};
-use std::io::BufReader;
-use std::path::PathBuf;
+use ramsh::engine::{
+    self, collect_candidates, count_candidates, format_duration, format_hash_lines,
+    format_passphrase, read_hash_lines, Attack, Benchmark, CancelToken, CandidateFilter,
+    Checkpoint, Checkpointer, Combinator, Cracker, FileIdentity, HashLineError, History,
+    HistoryEntry, HistoryOutcome, Hybrid, HybridSide, Mangled, MaskCandidates, MaskSpec,
+    NetworkParams, Outcome, PauseToken, Pbkdf2Backend, PmkidTarget, Potfile, RuleSet, SkipStats,
+    SkipToken, Status, Wordlist, WordlistQueue, BENCHMARK_STEP, MAX_PASSPHRASE_LEN,
+    MIN_PASSPHRASE_LEN, STATUS_INTERVAL,
+};
+
+use std::cell::{Cell, RefCell};
+use std::cmp::Reverse;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::application::RamshApplication;
 use crate::config::{APP_ID, PROFILE};
 
+/// How often a running crack writes its checkpoint to disk.
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The names hcxpcapngtool and hashcat users give hash files.
+const HASH_FILE_PATTERNS: [&str; 4] = ["*.22000", "*.22001", "*.hc22000", "*.16800"];
+
+/// How many of the lines of a hash file that couldn't be imported are
+/// listed, the rest only being counted.
+const MAX_SHOWN_ERRORS: usize = 10;
+
+/// Where the checkpoint of the current session lives.
+fn session_path() -> PathBuf {
+    engine::data_dir().join("session.json")
+}
+
+/// Where the record of past cracks lives.
+fn history_path() -> PathBuf {
+    engine::data_dir().join("history.json")
+}
+
+/// The crack running in the background, and the handles used to steer it.
+#[derive(Debug)]
+pub struct CrackJob {
+    cancel_token: CancelToken,
+    pause_token: PauseToken,
+    skip_token: SkipToken,
+    checkpointer: Option<Arc<Checkpointer>>,
+    /// The wordlist or mask length the workers are on, for the Skip button.
+    current_source: Cell<usize>,
+}
+
+/// Where the candidates of a queued wordlist come from.
+#[derive(Debug, Clone)]
+enum SourceKind {
+    File(PathBuf),
+    Text(String),
+}
+
+impl SourceKind {
+    fn name(&self) -> String {
+        match self {
+            Self::File(path) => path
+                .file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into_owned(),
+            Self::Text(_) => String::from("Pasted text"),
+        }
+    }
+
+    fn open(&self) -> io::Result<Wordlist> {
+        match self {
+            Self::File(path) => Wordlist::open(path),
+            Self::Text(text) => Ok(Wordlist::from_text(text.clone())),
+        }
+    }
+}
+
+/// A wordlist on the wordlist page, waiting for the crack to start.
+#[derive(Debug)]
+pub struct QueuedSource {
+    kind: SourceKind,
+    row: adw::ActionRow,
+    /// Set once the lines have been counted in the background.
+    lines: Option<u64>,
+    /// Stops the count if the wordlist is removed first.
+    counting: CancelToken,
+}
+
+impl Drop for QueuedSource {
+    fn drop(&mut self) {
+        self.counting.cancel();
+    }
+}
+
+/// The lines of a queued wordlist, for attacks whose progress counts
+/// candidates.
+enum LineCount {
+    Known(u64),
+    /// Counted when the attack starts, as the count in the background
+    /// hasn't finished or failed.
+    Pending(Wordlist),
+}
+
+impl LineCount {
+    fn get(self) -> io::Result<u64> {
+        match self {
+            Self::Known(lines) => Ok(lines),
+            Self::Pending(mut wordlist) => wordlist.count_lines(),
+        }
+    }
+}
+
+/// A network added on the network page, to be cracked along with the
+/// others.
+#[derive(Debug)]
+pub struct QueuedTarget {
+    target: PmkidTarget,
+    row: adw::ActionRow,
+}
+
+/// The rules picked on the wordlist page.
+#[derive(Debug)]
+pub struct RuleFile {
+    path: PathBuf,
+    rules: Arc<RuleSet>,
+}
+
+impl RuleFile {
+    fn name(&self) -> String {
+        self.path
+            .file_name()
+            .unwrap_or(self.path.as_os_str())
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// One of the two wordlists of the combinator page.
+#[derive(Debug, Clone, Copy)]
+enum CombinatorSide {
+    Left,
+    Right,
+}
+
+/// Where a rule file picked with [`RamshApplicationWindow::import_rule_file`]
+/// goes.
+#[derive(Debug, Clone, Copy)]
+enum RuleSlot {
+    Wordlist,
+    Combinator(CombinatorSide),
+}
+
 mod imp {
     use super::*;
 
@@ -31,11 +170,28 @@ mod imp {
     #[template(resource = "/com/github/fushinari/Ramsh/ui/window.ui")]
     pub struct RamshApplicationWindow {
         pub settings: gio::Settings,
+        pub crack_job: RefCell<Option<CrackJob>>,
+        pub network_targets: RefCell<Vec<QueuedTarget>>,
+        pub wordlist_sources: RefCell<Vec<QueuedSource>>,
+        pub wordlist_rules: RefCell<Option<RuleFile>>,
+        pub combinator_left: RefCell<Option<PathBuf>>,
+        pub combinator_right: RefCell<Option<PathBuf>>,
+        pub combinator_left_rules: RefCell<Option<RuleFile>>,
+        pub combinator_right_rules: RefCell<Option<RuleFile>>,
+        /// `None` if the history couldn't be read, so that it isn't
+        /// overwritten.
+        pub history: RefCell<Option<History>>,
+        /// The page to go back to from the history page.
+        pub history_return: RefCell<String>,
         #[template_child]
         pub main_stack: TemplateChild<adw::ViewStack>,
         // Welcome Page
         #[template_child]
         pub begin_btn: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub resume_btn: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub history_btn: TemplateChild<gtk::Button>,
         // Network Page
         #[template_child]
         pub network_next_btn: TemplateChild<gtk::Button>,
@@ -44,6 +200,10 @@ mod imp {
         #[template_child]
         pub network_import_btn: TemplateChild<gtk::Button>,
         #[template_child]
+        pub network_add_btn: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub network_export_btn: TemplateChild<gtk::Button>,
+        #[template_child]
         pub network_essid_entry: TemplateChild<adw::EntryRow>,
         #[template_child]
         pub network_bssid_entry: TemplateChild<adw::EntryRow>,
@@ -51,18 +211,114 @@ mod imp {
         pub network_sta_mac_entry: TemplateChild<adw::EntryRow>,
         #[template_child]
         pub network_pmkid_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub network_targets_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub network_targets_list: TemplateChild<gtk::ListBox>,
         // Wordlist Page
         #[template_child]
+        pub attack_stack: TemplateChild<adw::ViewStack>,
+        #[template_child]
         pub wordlist_next_btn: TemplateChild<gtk::Button>,
         #[template_child]
         pub wordlist_previous_btn: TemplateChild<gtk::Button>,
         #[template_child]
         pub wordlist_import_btn: TemplateChild<gtk::Button>,
         #[template_child]
+        pub wordlist_folder_btn: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub wordlist_sources_list: TemplateChild<gtk::ListBox>,
+        #[template_child]
         pub wordlist_text: TemplateChild<gtk::TextView>,
+        #[template_child]
+        pub wordlist_add_text_btn: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub wordlist_rules_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub wordlist_rules_btn: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub wordlist_rules_clear_btn: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub wordlist_mask_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub wordlist_dedupe_switch: TemplateChild<gtk::Switch>,
+        #[template_child]
+        pub wordlist_raw_psk_switch: TemplateChild<gtk::Switch>,
+        #[template_child]
+        pub mask_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub mask_charset1_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub mask_charset2_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub mask_charset3_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub mask_charset4_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub mask_increment_switch: TemplateChild<gtk::Switch>,
+        #[template_child]
+        pub mask_increment_min_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub mask_increment_min_spin: TemplateChild<gtk::SpinButton>,
+        #[template_child]
+        pub mask_keyspace_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub combinator_left_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub combinator_left_btn: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub combinator_right_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub combinator_right_btn: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub combinator_separator_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub combinator_left_rules_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub combinator_left_rules_btn: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub combinator_left_rules_clear_btn: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub combinator_right_rules_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub combinator_right_rules_btn: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub combinator_right_rules_clear_btn: TemplateChild<gtk::Button>,
         // Cracking Page
         #[template_child]
+        pub cracking_status_page: TemplateChild<adw::StatusPage>,
+        #[template_child]
         pub cracking_progress: TemplateChild<gtk::ProgressBar>,
+        #[template_child]
+        pub cracking_source_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub cracking_source_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub cracking_source_progress: TemplateChild<gtk::ProgressBar>,
+        #[template_child]
+        pub cracking_targets_clamp: TemplateChild<adw::Clamp>,
+        #[template_child]
+        pub cracking_targets_list: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub cracking_tried_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub cracking_speed_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub cracking_elapsed_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub cracking_eta_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub cracking_rules_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub cracking_rules_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub cracking_candidate_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub cracking_pause_btn: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub cracking_skip_btn: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub cracking_cancel_btn: TemplateChild<gtk::Button>,
         // Success Page
         #[template_child]
         pub success_another_btn: TemplateChild<gtk::Button>,
@@ -73,30 +329,108 @@ mod imp {
         pub failure_another_btn: TemplateChild<gtk::Button>,
         #[template_child]
         pub failure_status_page: TemplateChild<adw::StatusPage>,
+        // History Page
+        #[template_child]
+        pub history_back_btn: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub history_search_entry: TemplateChild<gtk::SearchEntry>,
+        #[template_child]
+        pub history_sort_dropdown: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub history_list: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub history_placeholder: TemplateChild<gtk::Label>,
     }
 
     impl Default for RamshApplicationWindow {
         fn default() -> Self {
             Self {
                 settings: gio::Settings::new(APP_ID),
+                crack_job: RefCell::default(),
+                network_targets: RefCell::default(),
+                wordlist_sources: RefCell::default(),
+                wordlist_rules: RefCell::default(),
+                combinator_left: RefCell::default(),
+                combinator_right: RefCell::default(),
+                combinator_left_rules: RefCell::default(),
+                combinator_right_rules: RefCell::default(),
+                history: RefCell::default(),
+                history_return: RefCell::default(),
                 main_stack: TemplateChild::default(),
                 begin_btn: TemplateChild::default(),
+                resume_btn: TemplateChild::default(),
+                history_btn: TemplateChild::default(),
                 network_next_btn: TemplateChild::default(),
                 network_previous_btn: TemplateChild::default(),
                 network_import_btn: TemplateChild::default(),
+                network_add_btn: TemplateChild::default(),
+                network_export_btn: TemplateChild::default(),
                 network_essid_entry: TemplateChild::default(),
                 network_bssid_entry: TemplateChild::default(),
                 network_sta_mac_entry: TemplateChild::default(),
                 network_pmkid_entry: TemplateChild::default(),
+                network_targets_box: TemplateChild::default(),
+                network_targets_list: TemplateChild::default(),
+                attack_stack: TemplateChild::default(),
                 wordlist_next_btn: TemplateChild::default(),
                 wordlist_previous_btn: TemplateChild::default(),
                 wordlist_import_btn: TemplateChild::default(),
+                wordlist_folder_btn: TemplateChild::default(),
+                wordlist_sources_list: TemplateChild::default(),
                 wordlist_text: TemplateChild::default(),
+                wordlist_add_text_btn: TemplateChild::default(),
+                wordlist_rules_row: TemplateChild::default(),
+                wordlist_rules_btn: TemplateChild::default(),
+                wordlist_rules_clear_btn: TemplateChild::default(),
+                wordlist_mask_row: TemplateChild::default(),
+                wordlist_dedupe_switch: TemplateChild::default(),
+                wordlist_raw_psk_switch: TemplateChild::default(),
+                mask_entry: TemplateChild::default(),
+                mask_charset1_entry: TemplateChild::default(),
+                mask_charset2_entry: TemplateChild::default(),
+                mask_charset3_entry: TemplateChild::default(),
+                mask_charset4_entry: TemplateChild::default(),
+                mask_increment_switch: TemplateChild::default(),
+                mask_increment_min_row: TemplateChild::default(),
+                mask_increment_min_spin: TemplateChild::default(),
+                mask_keyspace_label: TemplateChild::default(),
+                combinator_left_row: TemplateChild::default(),
+                combinator_left_btn: TemplateChild::default(),
+                combinator_right_row: TemplateChild::default(),
+                combinator_right_btn: TemplateChild::default(),
+                combinator_separator_entry: TemplateChild::default(),
+                combinator_left_rules_row: TemplateChild::default(),
+                combinator_left_rules_btn: TemplateChild::default(),
+                combinator_left_rules_clear_btn: TemplateChild::default(),
+                combinator_right_rules_row: TemplateChild::default(),
+                combinator_right_rules_btn: TemplateChild::default(),
+                combinator_right_rules_clear_btn: TemplateChild::default(),
+                cracking_status_page: TemplateChild::default(),
                 cracking_progress: TemplateChild::default(),
+                cracking_source_box: TemplateChild::default(),
+                cracking_source_label: TemplateChild::default(),
+                cracking_source_progress: TemplateChild::default(),
+                cracking_targets_clamp: TemplateChild::default(),
+                cracking_targets_list: TemplateChild::default(),
+                cracking_tried_label: TemplateChild::default(),
+                cracking_speed_label: TemplateChild::default(),
+                cracking_elapsed_label: TemplateChild::default(),
+                cracking_eta_label: TemplateChild::default(),
+                cracking_rules_row: TemplateChild::default(),
+                cracking_rules_label: TemplateChild::default(),
+                cracking_candidate_label: TemplateChild::default(),
+                cracking_pause_btn: TemplateChild::default(),
+                cracking_skip_btn: TemplateChild::default(),
+                cracking_cancel_btn: TemplateChild::default(),
                 success_another_btn: TemplateChild::default(),
                 success_status_page: TemplateChild::default(),
                 failure_another_btn: TemplateChild::default(),
                 failure_status_page: TemplateChild::default(),
+                history_back_btn: TemplateChild::default(),
+                history_search_entry: TemplateChild::default(),
+                history_sort_dropdown: TemplateChild::default(),
+                history_list: TemplateChild::default(),
+                history_placeholder: TemplateChild::default(),
             }
         }
     }
@@ -139,6 +473,18 @@ mod imp {
                 log::warn!("Failed to save window state, {}", &err);
             }
 
+            // Keep the progress of a running crack so it can be resumed
+            if let Some(checkpointer) = self
+                .crack_job
+                .borrow()
+                .as_ref()
+                .and_then(|job| job.checkpointer.as_ref())
+            {
+                if let Err(err) = checkpointer.save() {
+                    log::warn!("Failed to save checkpoint, {}", &err);
+                }
+            }
+
             // Pass close request on to the parent
             self.parent_close_request(window)
         }
@@ -159,6 +505,9 @@ impl RamshApplicationWindow {
         let window: Self = glib::Object::new(&[("application", app)])
             .expect("Failed to create RamshApplicationWindow");
         window.setup_signals();
+        window.load_history();
+        window.update_resume_btn();
+        window.update_mask_keyspace();
         window
     }
 
@@ -170,11 +519,29 @@ impl RamshApplicationWindow {
             .connect_clicked(clone!(@weak self as win => move |_| {
                 win.page_switch("network_page");
             }));
+        imp.resume_btn
+            .connect_clicked(clone!(@weak self as win => move |_| {
+                win.resume_session();
+            }));
+        imp.history_btn
+            .connect_clicked(clone!(@weak self as win => move |_| {
+                win.show_history();
+            }));
+        let action_history = gio::SimpleAction::new("show-history", None);
+        action_history.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.show_history();
+        }));
+        self.add_action(&action_history);
 
         // Network Page
         imp.network_next_btn
             .connect_clicked(clone!(@weak self as win => move |_| {
                 let imp = win.imp();
+                // The entries may be left empty once there are other targets
+                if !imp.network_targets.borrow().is_empty() && win.network_entries_empty() {
+                    win.page_switch("wordlist_page");
+                    return;
+                }
                 if imp.network_essid_entry.text().is_empty() {
                     imp.network_essid_entry.add_css_class("error");
                     return;
@@ -192,11 +559,23 @@ impl RamshApplicationWindow {
             }));
         imp.network_previous_btn
             .connect_clicked(clone!(@weak self as win => move |_| {
+                win.update_resume_btn();
                 win.page_switch("welcome_page");
             }));
         imp.network_import_btn
             .connect_clicked(clone!(@weak self as win => move |_| {
-                win.import_network_json();
+                win.import_network_file();
+            }));
+        imp.network_export_btn
+            .connect_clicked(clone!(@weak self as win => move |_| {
+                win.export_network_targets();
+            }));
+        imp.network_add_btn
+            .connect_clicked(clone!(@weak self as win => move |_| {
+                if let Some(target) = win.network_target() {
+                    win.set_network_entries(None);
+                    win.add_network_target(target);
+                }
             }));
         imp.network_essid_entry
             .connect_changed(clone!(@weak self as win => move |_| {
@@ -217,13 +596,46 @@ impl RamshApplicationWindow {
             .connect_changed(clone!(@weak self as win => move |_| {
                 let imp = win.imp();
                 imp.network_pmkid_entry.remove_css_class("error");
+                imp.network_pmkid_entry.set_tooltip_text(None);
+
+                // A pasted hash line, of either format, fills in the rest
+                // of the page
+                let text = imp.network_pmkid_entry.text();
+                if text.contains('*') {
+                    match PmkidTarget::from_hash_line(&text) {
+                        Ok(target) => {
+                            // Not from within this handler, as it sets the
+                            // text of this entry too
+                            glib::idle_add_local_once(clone!(@weak win => move || {
+                                win.set_network_entries(Some(&target));
+                            }));
+                        }
+                        Err(err) => {
+                            imp.network_pmkid_entry.add_css_class("error");
+                            imp.network_pmkid_entry
+                                .set_tooltip_text(Some(&err.to_string()));
+                        }
+                    }
+                }
             }));
 
         // Wordlist Page
+        imp.attack_stack.connect_visible_child_name_notify(
+            clone!(@weak self as win => move |stack| {
+                let imp = win.imp();
+                let is_wordlist = stack.visible_child_name().as_deref() == Some("wordlist");
+                imp.wordlist_import_btn.set_visible(is_wordlist);
+                imp.wordlist_folder_btn.set_visible(is_wordlist);
+            }),
+        );
         imp.wordlist_next_btn
             .connect_clicked(clone!(@weak self as win => move |_| {
-                win.page_switch("cracking_page");
-                win.complete_wordlist_process(None);
+                let imp = win.imp();
+                match imp.attack_stack.visible_child_name().as_deref() {
+                    Some("mask") => win.complete_mask_process(),
+                    Some("combinator") => win.complete_combinator_process(),
+                    _ => win.complete_wordlist_process(),
+                }
             }));
         imp.wordlist_previous_btn
             .connect_clicked(clone!(@weak self as win => move |_| {
@@ -233,10 +645,111 @@ impl RamshApplicationWindow {
             .connect_clicked(clone!(@weak self as win => move |_| {
                 win.import_wordlist();
             }));
+        imp.wordlist_folder_btn
+            .connect_clicked(clone!(@weak self as win => move |_| {
+                win.import_wordlist_folder();
+            }));
+        imp.wordlist_add_text_btn
+            .connect_clicked(clone!(@weak self as win => move |_| {
+                win.add_wordlist_text();
+            }));
+        imp.wordlist_text
+            .buffer()
+            .connect_changed(clone!(@weak self as win => move |_| {
+                let imp = win.imp();
+                imp.wordlist_text.remove_css_class("error");
+            }));
+        imp.wordlist_rules_btn
+            .connect_clicked(clone!(@weak self as win => move |_| {
+                win.import_rule_file(RuleSlot::Wordlist);
+            }));
+        imp.wordlist_rules_clear_btn
+            .connect_clicked(clone!(@weak self as win => move |_| {
+                win.set_rule_file(RuleSlot::Wordlist, None);
+            }));
+        imp.wordlist_mask_row
+            .connect_selected_notify(clone!(@weak self as win => move |_| {
+                // Rules and masks don't mix, the words have to be counted
+                let imp = win.imp();
+                imp.wordlist_rules_row
+                    .set_sensitive(win.hybrid_side().is_none());
+                win.update_mask_keyspace();
+            }));
+        imp.settings
+            .bind("dedupe-candidates", &*imp.wordlist_dedupe_switch, "active")
+            .build();
+        imp.settings
+            .bind("accept-raw-psk", &*imp.wordlist_raw_psk_switch, "active")
+            .build();
+        for entry in [
+            &*imp.mask_entry,
+            &*imp.mask_charset1_entry,
+            &*imp.mask_charset2_entry,
+            &*imp.mask_charset3_entry,
+            &*imp.mask_charset4_entry,
+        ] {
+            entry.connect_changed(clone!(@weak self as win => move |_| {
+                win.update_mask_keyspace();
+            }));
+        }
+        imp.mask_increment_switch
+            .bind_property("active", &*imp.mask_increment_min_row, "sensitive")
+            .build();
+        imp.mask_increment_switch
+            .connect_active_notify(clone!(@weak self as win => move |_| {
+                win.update_mask_keyspace();
+            }));
+        imp.mask_increment_min_spin
+            .connect_value_changed(clone!(@weak self as win => move |_| {
+                win.update_mask_keyspace();
+            }));
+        for side in [CombinatorSide::Left, CombinatorSide::Right] {
+            let (row, btn, rules_btn, rules_clear_btn) = match side {
+                CombinatorSide::Left => (
+                    &imp.combinator_left_row,
+                    &imp.combinator_left_btn,
+                    &imp.combinator_left_rules_btn,
+                    &imp.combinator_left_rules_clear_btn,
+                ),
+                CombinatorSide::Right => (
+                    &imp.combinator_right_row,
+                    &imp.combinator_right_btn,
+                    &imp.combinator_right_rules_btn,
+                    &imp.combinator_right_rules_clear_btn,
+                ),
+            };
+            let row = row.get();
+            btn.connect_clicked(clone!(@weak self as win, @weak row => move |_| {
+                row.remove_css_class("error");
+                win.import_combinator_wordlist(side);
+            }));
+            rules_btn.connect_clicked(clone!(@weak self as win => move |_| {
+                win.import_rule_file(RuleSlot::Combinator(side));
+            }));
+            rules_clear_btn.connect_clicked(clone!(@weak self as win => move |_| {
+                win.set_rule_file(RuleSlot::Combinator(side), None);
+            }));
+        }
+
+        // Cracking Page
+        imp.cracking_pause_btn
+            .connect_clicked(clone!(@weak self as win => move |_| {
+                win.toggle_pause();
+            }));
+        imp.cracking_skip_btn
+            .connect_clicked(clone!(@weak self as win => move |_| {
+                win.skip_source();
+            }));
+        imp.cracking_cancel_btn
+            .connect_clicked(clone!(@weak self as win => move |_| {
+                win.cancel_cracking();
+                win.page_switch("wordlist_page");
+            }));
 
         // Success Page
         imp.success_another_btn
             .connect_clicked(clone!(@weak self as win => move |_| {
+                win.cancel_cracking();
                 win.reset();
                 win.page_switch("network_page");
             }));
@@ -244,17 +757,231 @@ impl RamshApplicationWindow {
         // Failure Page
         imp.failure_another_btn
             .connect_clicked(clone!(@weak self as win => move |_| {
+                win.cancel_cracking();
                 win.page_switch("network_page");
             }));
+
+        // History Page
+        imp.history_back_btn
+            .connect_clicked(clone!(@weak self as win => move |_| {
+                let page = win.imp().history_return.take();
+                win.update_resume_btn();
+                win.page_switch(&page);
+            }));
+        imp.history_search_entry
+            .connect_search_changed(clone!(@weak self as win => move |_| {
+                win.refresh_history();
+            }));
+        imp.history_sort_dropdown
+            .connect_selected_notify(clone!(@weak self as win => move |_| {
+                win.refresh_history();
+            }));
     }
 
     fn reset(&self) {
         let imp = self.imp();
-        imp.network_essid_entry.set_text("");
-        imp.network_bssid_entry.set_text("");
-        imp.network_sta_mac_entry.set_text("");
-        imp.network_pmkid_entry.set_text("");
+        self.set_network_entries(None);
+        self.clear_network_targets();
         imp.wordlist_text.buffer().set_text("");
+        self.clear_wordlist_sources();
+        imp.mask_entry.set_text("");
+        self.set_combinator_wordlist(CombinatorSide::Left, None);
+        self.set_combinator_wordlist(CombinatorSide::Right, None);
+        imp.combinator_separator_entry.set_text("");
+    }
+
+    /// Stops the running crack, if any, and forgets its checkpoint.
+    ///
+    /// The workers notice the token and stop, and the receivers attached in
+    /// `start_cracking` detach themselves instead of touching the UI again.
+    fn cancel_cracking(&self) {
+        let imp = self.imp();
+        if let Some(job) = imp.crack_job.take() {
+            job.cancel_token.cancel();
+            if let Some(checkpointer) = job.checkpointer {
+                if let Err(err) = checkpointer.remove() {
+                    log::warn!("Failed to remove checkpoint, {}", &err);
+                }
+            }
+        }
+        self.set_paused(false);
+        self.reset_cracking_status();
+    }
+
+    fn toggle_pause(&self) {
+        let imp = self.imp();
+        let job = imp.crack_job.borrow();
+        let job = match job.as_ref() {
+            Some(job) => job,
+            None => return,
+        };
+
+        if job.pause_token.is_paused() {
+            job.pause_token.resume();
+            self.set_paused(false);
+        } else {
+            job.pause_token.pause();
+            self.set_paused(true);
+            if let Some(checkpointer) = &job.checkpointer {
+                if let Err(err) = checkpointer.save() {
+                    log::warn!("Failed to save checkpoint, {}", &err);
+                }
+            }
+        }
+    }
+
+    /// Drops the rest of the wordlist being tried and moves on to the next.
+    fn skip_source(&self) {
+        let imp = self.imp();
+        if let Some(job) = imp.crack_job.borrow().as_ref() {
+            job.skip_token.skip(job.current_source.get());
+        }
+    }
+
+    fn set_paused(&self, paused: bool) {
+        let imp = self.imp();
+        if paused {
+            imp.cracking_status_page.set_title("Paused");
+            imp.cracking_pause_btn.set_label("Resume");
+        } else {
+            imp.cracking_status_page.set_title("Cracking now...");
+            imp.cracking_pause_btn.set_label("Pause");
+        }
+    }
+
+    fn update_resume_btn(&self) {
+        let has_session = matches!(Checkpoint::load(&session_path()), Ok(Some(_)));
+        self.imp().resume_btn.set_visible(has_session);
+    }
+
+    fn resume_session(&self) {
+        let imp = self.imp();
+
+        let checkpoint = match Checkpoint::load(&session_path()) {
+            Ok(Some(checkpoint)) => checkpoint,
+            Ok(None) => {
+                self.update_resume_btn();
+                return;
+            }
+            Err(err) => {
+                self.show_error(&format!("Couldn't read the previous session: {}", err));
+                return;
+            }
+        };
+
+        self.clear_network_targets();
+        match &checkpoint.targets[..] {
+            [target] => self.set_network_entries(Some(target)),
+            targets => {
+                self.set_network_entries(None);
+                for target in targets {
+                    self.add_network_target(target.clone());
+                }
+            }
+        }
+
+        match checkpoint.verify() {
+            Ok(true) => {}
+            Ok(false) => {
+                self.show_error("A wordlist has changed since the session was saved");
+                return;
+            }
+            Err(err) => {
+                self.show_error(&format!("Couldn't check the wordlists: {}", err));
+                return;
+            }
+        }
+
+        // The switches are bound to the settings the crack reads them from
+        imp.wordlist_dedupe_switch
+            .set_active(checkpoint.filter.dedupe);
+        imp.wordlist_raw_psk_switch
+            .set_active(checkpoint.filter.raw_psk);
+
+        let targets = checkpoint.targets.clone();
+        match &checkpoint.attack {
+            Attack::Wordlist { wordlists, rules } => {
+                let rule_file = match load_rule_file(rules.as_ref()) {
+                    Ok(rule_file) => rule_file,
+                    Err(err) => {
+                        self.show_error(&err);
+                        return;
+                    }
+                };
+                self.set_rule_file(RuleSlot::Wordlist, rule_file);
+
+                self.clear_wordlist_sources();
+                for wordlist in wordlists {
+                    self.add_wordlist_source(SourceKind::File(wordlist.path.clone()));
+                }
+
+                self.set_hybrid_side(None);
+
+                imp.attack_stack.set_visible_child_name("wordlist");
+                self.page_switch("cracking_page");
+                self.start_wordlist_attack(targets, Some(checkpoint));
+            }
+            Attack::Mask { mask } => {
+                let spec = mask.clone();
+                self.set_mask_spec(&spec);
+
+                imp.attack_stack.set_visible_child_name("mask");
+                self.page_switch("cracking_page");
+                self.start_mask_attack(targets, &spec, Some(checkpoint));
+            }
+            Attack::Hybrid {
+                wordlists,
+                mask,
+                side,
+            } => {
+                let (spec, side) = (mask.clone(), *side);
+                self.set_mask_spec(&spec);
+                self.set_hybrid_side(Some(side));
+
+                self.clear_wordlist_sources();
+                for wordlist in wordlists {
+                    self.add_wordlist_source(SourceKind::File(wordlist.path.clone()));
+                }
+
+                imp.attack_stack.set_visible_child_name("wordlist");
+                self.page_switch("cracking_page");
+                self.start_hybrid_attack(targets, &spec, side, Some(checkpoint));
+            }
+            Attack::Combinator {
+                left,
+                right,
+                separator,
+                left_rules,
+                right_rules,
+            } => {
+                let rule_files = load_rule_file(left_rules.as_ref()).and_then(|left| {
+                    load_rule_file(right_rules.as_ref()).map(|right| (left, right))
+                });
+                let (left_rules, right_rules) = match rule_files {
+                    Ok(rule_files) => rule_files,
+                    Err(err) => {
+                        self.show_error(&err);
+                        return;
+                    }
+                };
+                self.set_rule_file(RuleSlot::Combinator(CombinatorSide::Left), left_rules);
+                self.set_rule_file(RuleSlot::Combinator(CombinatorSide::Right), right_rules);
+
+                self.set_combinator_wordlist(CombinatorSide::Left, Some(left.path.clone()));
+                self.set_combinator_wordlist(CombinatorSide::Right, Some(right.path.clone()));
+                imp.combinator_separator_entry.set_text(separator);
+
+                imp.attack_stack.set_visible_child_name("combinator");
+                self.page_switch("cracking_page");
+                self.start_combinator_attack(targets, Some(checkpoint));
+            }
+        }
+    }
+
+    fn show_error(&self, message: &str) {
+        let imp = self.imp();
+        imp.failure_status_page.set_description(Some(message));
+        self.page_switch("failure_page");
     }
 
     fn page_switch(&self, page: &str) {
@@ -262,7 +989,7 @@ impl RamshApplicationWindow {
         imp.main_stack.set_visible_child_name(page);
     }
 
-    fn import_network_json(&self) {
+    fn import_network_file(&self) {
         let dialog = gtk::FileChooserNative::new(
             Some("Import Network File"),
             Some(self),
@@ -272,120 +999,1166 @@ impl RamshApplicationWindow {
         );
         dialog.set_modal(true);
 
+        let any_filter = gtk::FileFilter::new();
+        any_filter.add_mime_type("application/json");
+        for pattern in HASH_FILE_PATTERNS {
+            any_filter.add_pattern(pattern);
+        }
+        any_filter.set_name(Some("Network and Hash Files"));
+        dialog.add_filter(&any_filter);
+
         let json_filter = gtk::FileFilter::new();
         json_filter.add_mime_type("application/json");
         json_filter.set_name(Some("JSON"));
         dialog.add_filter(&json_filter);
 
+        let hash_filter = gtk::FileFilter::new();
+        for pattern in HASH_FILE_PATTERNS {
+            hash_filter.add_pattern(pattern);
+        }
+        hash_filter.set_name(Some("Hashcat Hash Files"));
+        dialog.add_filter(&hash_filter);
+
         dialog.connect_response(clone!(@weak self as win => move |d, response| {
             if response == gtk::ResponseType::Accept {
                 let file = &d.file().expect("Couldn't get file");
 
                 let filename = file.path().expect("Couldn't get file path");
-                let file = File::open(&filename.as_path()).expect("Couldn't open file");
-                win.set_network_params(&file);
-                win.page_switch("wordlist_page");
+                if win.load_network_file(&filename) {
+                    win.page_switch("wordlist_page");
+                }
             }
             d.destroy();
         }));
         dialog.show();
     }
 
-    fn set_network_params(&self, file: &File) {
-        let imp = self.imp();
-
-        let reader = BufReader::new(file);
-        let json: serde_json::Value =
-            serde_json::from_reader(reader).expect("[pmkid] file should be proper JSON!");
-
-        let essid = json
-            .get("essid")
-            .unwrap()
-            .as_str()
-            .expect("[pmkid] config json should have essid key");
-
-        let bssid = json
-            .get("bssid")
-            .unwrap()
-            .as_str()
-            .expect("[pmkid] config json should have bssid key");
-
-        let sta_mac = json
-            .get("sta_mac")
-            .unwrap()
-            .as_str()
-            .expect("[pmkid] config json should have sta_mac key");
-
-        let pmkid = json
-            .get("pmkid")
-            .unwrap()
-            .as_str()
-            .expect("[pmkid] config json should have pmkid key");
-
-        imp.network_essid_entry.set_text(essid);
-        imp.network_bssid_entry.set_text(bssid);
-        imp.network_sta_mac_entry.set_text(sta_mac);
-        imp.network_pmkid_entry.set_text(pmkid);
-    }
+    /// Saves the targets of the network page as a hash file of mode 16800,
+    /// for hashcat or another import.
+    fn export_network_targets(&self) {
+        let targets = match self.network_targets() {
+            Some(targets) => targets,
+            None => return,
+        };
 
-    fn import_wordlist(&self) {
         let dialog = gtk::FileChooserNative::new(
-            Some("Import Wordlist File"),
+            Some("Export Hash Lines"),
             Some(self),
-            gtk::FileChooserAction::Open,
-            Some("Import"),
+            gtk::FileChooserAction::Save,
+            Some("Export"),
             Some("Cancel"),
         );
         dialog.set_modal(true);
-
-        let text_filter = gtk::FileFilter::new();
-        text_filter.add_mime_type("text/*");
-        text_filter.set_name(Some("Text Files"));
-        dialog.add_filter(&text_filter);
+        dialog.set_current_name("targets.16800");
 
         dialog.connect_response(clone!(@weak self as win => move |d, response| {
             if response == gtk::ResponseType::Accept {
                 let file = &d.file().expect("Couldn't get file");
-                let filename = file.path().expect("Couldn't get file path");
-
-                let imp = win.imp();
-                imp.main_stack.set_visible_child_name("cracking_page");
-
-                win.complete_wordlist_process(Some(filename));
+                let path = file.path().expect("Couldn't get file path");
+                if let Err(err) = fs::write(&path, format_hash_lines(&targets)) {
+                    win.show_error_dialog(
+                        "Couldn't Export Hash Lines",
+                        &format!("{}: {}", path.display(), err),
+                    );
+                }
             }
             d.destroy();
         }));
         dialog.show();
     }
 
-    fn complete_wordlist_process(&self, path: Option<PathBuf>) {
+    /// Fills the network page in from a JSON network file, or from a file
+    /// of hash lines, which may hold many targets. Returns whether there is
+    /// anything to go on to the wordlists with.
+    fn load_network_file(&self, path: &Path) -> bool {
         let imp = self.imp();
 
-        let buffer = imp.wordlist_text.buffer();
-        // avoid having to load the text file into the UI if using import
-        // this avoids unnecessary overhead
-        let text = match path {
-            None => buffer
-                .text(&buffer.start_iter(), &buffer.end_iter(), true)
-                .to_string(),
-            Some(file_path) => read_to_string(&file_path.as_path()).unwrap(),
-        };
-        let newline_split = text.lines().map(str::to_string);
-        let wordlist_dict: Vec<String> = newline_split.collect();
-
-        self.get_result(
-            imp.network_essid_entry.text().to_string(),
-            imp.network_bssid_entry.text().to_string(),
-            imp.network_sta_mac_entry.text().to_string(),
-            imp.network_pmkid_entry.text().to_string(),
-            wordlist_dict,
-        );
-    }
-
-    fn save_window_size(&self) -> Result<(), glib::BoolError> {
-        let imp = self.imp();
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                self.show_error_dialog(
+                    "Couldn't Read Network File",
+                    &format!("{}: {}", path.display(), err),
+                );
+                return false;
+            }
+        };
+        if contents.trim_start().starts_with('{') {
+            return self.set_network_params(path, &contents);
+        }
 
-        let (width, height) = self.default_size();
+        // A single target goes in the entries, unless there are others
+        let (targets, errors) = read_hash_lines(&contents);
+        match &targets[..] {
+            [target] if imp.network_targets.borrow().is_empty() => {
+                self.set_network_entries(Some(target))
+            }
+            targets => {
+                for target in targets {
+                    self.add_network_target(target.clone());
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            self.show_hash_line_errors(path, &errors);
+            return false;
+        }
+        if targets.is_empty() {
+            self.show_error_dialog(
+                "Couldn't Use Network File",
+                &format!("{} has no hash lines in it.", path.display()),
+            );
+            return false;
+        }
+        true
+    }
+
+    /// Lists the lines of the hash file at `path` that were left out, and
+    /// why.
+    fn show_hash_line_errors(&self, path: &Path, errors: &[HashLineError]) {
+        let mut body = errors
+            .iter()
+            .take(MAX_SHOWN_ERRORS)
+            .map(|err| format!("Line {}: {}", err.line, err.error))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if errors.len() > MAX_SHOWN_ERRORS {
+            body.push_str(&format!("\nand {} more", errors.len() - MAX_SHOWN_ERRORS));
+        }
+
+        let heading = format!(
+            "Skipped {} of {}",
+            if errors.len() == 1 {
+                String::from("1 Line")
+            } else {
+                format!("{} Lines", errors.len())
+            },
+            SourceKind::File(path.to_owned()).name()
+        );
+        self.show_error_dialog(&heading, &body);
+    }
+
+    /// Tells the user about a file that couldn't be used, without leaving
+    /// the page they are on.
+    fn show_error_dialog(&self, heading: &str, body: &str) {
+        let dialog = adw::MessageDialog::new(Some(self), Some(heading), Some(body));
+        dialog.add_response("close", "Close");
+        dialog.present();
+    }
+
+    /// Fills the network page in from the JSON network file at `path`.
+    fn set_network_params(&self, path: &Path, contents: &str) -> bool {
+        let imp = self.imp();
+
+        let params = match NetworkParams::from_reader(contents.as_bytes()) {
+            Ok(params) => params,
+            Err(err) => {
+                self.show_error_dialog(
+                    "Couldn't Read Network File",
+                    &format!("{}: {}", path.display(), err),
+                );
+                return false;
+            }
+        };
+
+        imp.network_essid_entry.set_text(&params.essid);
+        imp.network_bssid_entry.set_text(&params.bssid);
+        imp.network_sta_mac_entry.set_text(&params.sta_mac);
+        imp.network_pmkid_entry.set_text(&params.pmkid);
+        true
+    }
+
+    fn import_wordlist(&self) {
+        let dialog = gtk::FileChooserNative::new(
+            Some("Add Wordlist Files"),
+            Some(self),
+            gtk::FileChooserAction::Open,
+            Some("Add"),
+            Some("Cancel"),
+        );
+        dialog.set_modal(true);
+        dialog.set_select_multiple(true);
+
+        let text_filter = gtk::FileFilter::new();
+        text_filter.add_mime_type("text/*");
+        text_filter.set_name(Some("Text Files"));
+        dialog.add_filter(&text_filter);
+
+        let compressed_filter = gtk::FileFilter::new();
+        compressed_filter.add_mime_type("application/gzip");
+        compressed_filter.add_mime_type("application/x-bzip2");
+        compressed_filter.add_mime_type("application/x-xz");
+        compressed_filter.add_mime_type("application/zstd");
+        compressed_filter.set_name(Some("Compressed Wordlists"));
+        dialog.add_filter(&compressed_filter);
+
+        dialog.connect_response(clone!(@weak self as win => move |d, response| {
+            if response == gtk::ResponseType::Accept {
+                let files = d.files();
+                for index in 0..files.n_items() {
+                    let path = files
+                        .item(index)
+                        .and_then(|file| file.downcast::<gio::File>().ok())
+                        .and_then(|file| file.path());
+                    if let Some(path) = path {
+                        win.add_wordlist_source(SourceKind::File(path));
+                    }
+                }
+            }
+            d.destroy();
+        }));
+        dialog.show();
+    }
+
+    fn import_wordlist_folder(&self) {
+        let dialog = gtk::FileChooserNative::new(
+            Some("Add Wordlist Folder"),
+            Some(self),
+            gtk::FileChooserAction::SelectFolder,
+            Some("Add"),
+            Some("Cancel"),
+        );
+        dialog.set_modal(true);
+
+        dialog.connect_response(clone!(@weak self as win => move |d, response| {
+            if response == gtk::ResponseType::Accept {
+                let file = &d.file().expect("Couldn't get folder");
+                let folder = file.path().expect("Couldn't get folder path");
+                win.add_wordlist_folder(&folder);
+            }
+            d.destroy();
+        }));
+        dialog.show();
+    }
+
+    /// Queues every file in `folder`, sorted by name. Hidden files and
+    /// subfolders are left out.
+    fn add_wordlist_folder(&self, folder: &Path) {
+        let entries = match fs::read_dir(folder) {
+            Ok(entries) => entries,
+            Err(err) => {
+                log::warn!("Failed to read wordlist folder, {}", &err);
+                return;
+            }
+        };
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.is_file()
+                    && !path
+                        .file_name()
+                        .is_none_or(|name| name.to_string_lossy().starts_with('.'))
+            })
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            self.add_wordlist_source(SourceKind::File(path));
+        }
+    }
+
+    /// Moves the pasted candidates into the queue, leaving the text view
+    /// empty. Returns whether there was anything to add.
+    fn add_wordlist_text(&self) -> bool {
+        let imp = self.imp();
+        let buffer = imp.wordlist_text.buffer();
+        let text = buffer
+            .text(&buffer.start_iter(), &buffer.end_iter(), true)
+            .to_string();
+        if text.is_empty() {
+            return false;
+        }
+
+        buffer.set_text("");
+        self.add_wordlist_source(SourceKind::Text(text));
+        true
+    }
+
+    /// Puts a wordlist at the end of the queue, and counts its lines in the
+    /// background.
+    fn add_wordlist_source(&self, kind: SourceKind) {
+        let imp = self.imp();
+
+        let row = adw::ActionRow::new();
+        row.set_title(&glib::markup_escape_text(&kind.name()));
+        row.set_subtitle("Counting lines...");
+        if let SourceKind::File(path) = &kind {
+            row.set_tooltip_text(Some(&path.display().to_string()));
+        }
+
+        let up_btn = gtk::Button::from_icon_name("go-up-symbolic");
+        up_btn.set_tooltip_text(Some("Move Up"));
+        up_btn.set_valign(gtk::Align::Center);
+        up_btn.add_css_class("flat");
+        up_btn.connect_clicked(clone!(@weak self as win, @weak row => move |_| {
+            win.move_wordlist_source_up(&row);
+        }));
+        row.add_suffix(&up_btn);
+
+        let remove_btn = gtk::Button::from_icon_name("user-trash-symbolic");
+        remove_btn.set_tooltip_text(Some("Remove"));
+        remove_btn.set_valign(gtk::Align::Center);
+        remove_btn.add_css_class("flat");
+        remove_btn.connect_clicked(clone!(@weak self as win, @weak row => move |_| {
+            win.remove_wordlist_source(&row);
+        }));
+        row.add_suffix(&remove_btn);
+
+        // Counting means reading the whole list, which can take a while.
+        // The count is kept for attacks that need it.
+        let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+        let counted = kind.clone();
+        let counting = CancelToken::new();
+        let cancel = counting.clone();
+        thread::spawn(move || {
+            let lines = counted
+                .open()
+                .and_then(|mut wordlist| wordlist.count_lines_until(&cancel));
+            let _ = sender.send(lines);
+        });
+        receiver.attach(
+            None,
+            clone!(@weak self as win, @weak row => @default-return glib::Continue(false), move |lines| {
+                match &lines {
+                    Ok(1) => row.set_subtitle("1 line"),
+                    Ok(lines) => row.set_subtitle(&format!("{} lines", lines)),
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                    Err(err) => row.set_subtitle(&glib::markup_escape_text(&err.to_string())),
+                }
+                if let Some(source) = win
+                    .imp()
+                    .wordlist_sources
+                    .borrow_mut()
+                    .iter_mut()
+                    .find(|source| source.row == row)
+                {
+                    source.lines = lines.ok();
+                }
+                glib::Continue(false)
+            }),
+        );
+
+        imp.wordlist_sources_list.append(&row);
+        imp.wordlist_sources.borrow_mut().push(QueuedSource {
+            kind,
+            row,
+            lines: None,
+            counting,
+        });
+    }
+
+    fn move_wordlist_source_up(&self, row: &adw::ActionRow) {
+        let imp = self.imp();
+        let mut sources = imp.wordlist_sources.borrow_mut();
+        let index = match sources.iter().position(|source| source.row == *row) {
+            Some(index) if index > 0 => index,
+            _ => return,
+        };
+
+        sources.swap(index - 1, index);
+        imp.wordlist_sources_list.remove(row);
+        imp.wordlist_sources_list.insert(row, index as i32 - 1);
+    }
+
+    fn remove_wordlist_source(&self, row: &adw::ActionRow) {
+        let imp = self.imp();
+        imp.wordlist_sources
+            .borrow_mut()
+            .retain(|source| source.row != *row);
+        imp.wordlist_sources_list.remove(row);
+    }
+
+    fn clear_wordlist_sources(&self) {
+        let imp = self.imp();
+        for source in imp.wordlist_sources.take() {
+            imp.wordlist_sources_list.remove(&source.row);
+        }
+    }
+
+    fn import_rule_file(&self, slot: RuleSlot) {
+        let dialog = gtk::FileChooserNative::new(
+            Some("Choose Rule File"),
+            Some(self),
+            gtk::FileChooserAction::Open,
+            Some("Choose"),
+            Some("Cancel"),
+        );
+        dialog.set_modal(true);
+
+        let rule_filter = gtk::FileFilter::new();
+        rule_filter.add_pattern("*.rule");
+        rule_filter.add_pattern("*.rules");
+        rule_filter.add_mime_type("text/*");
+        rule_filter.set_name(Some("Rule Files"));
+        dialog.add_filter(&rule_filter);
+
+        dialog.connect_response(clone!(@weak self as win => move |d, response| {
+            if response == gtk::ResponseType::Accept {
+                let file = &d.file().expect("Couldn't get file");
+                let path = file.path().expect("Couldn't get file path");
+                match RuleSet::load(&path) {
+                    Ok(rules) if !rules.is_empty() => win.set_rule_file(slot, Some(RuleFile {
+                        path,
+                        rules: Arc::new(rules),
+                    })),
+                    Ok(rules) => {
+                        let body = match rules.invalid().first() {
+                            Some((line, err)) => format!(
+                                "{} has no valid rules. Line {}: {}",
+                                path.display(),
+                                line,
+                                err
+                            ),
+                            None => format!("{} has no rules in it.", path.display()),
+                        };
+                        win.show_error_dialog("Couldn't Use Rule File", &body);
+                    }
+                    Err(err) => win.show_error_dialog(
+                        "Couldn't Read Rule File",
+                        &format!("{}: {}", path.display(), err),
+                    ),
+                }
+            }
+            d.destroy();
+        }));
+        dialog.show();
+    }
+
+    /// Mangles every candidate of the next crack, or one side of a
+    /// combinator, with `rule_file`, or tries them as they are if `None`.
+    fn set_rule_file(&self, slot: RuleSlot, rule_file: Option<RuleFile>) {
+        let imp = self.imp();
+        let (rules, row, clear_btn, unset) = match slot {
+            RuleSlot::Wordlist => (
+                &imp.wordlist_rules,
+                &imp.wordlist_rules_row,
+                &imp.wordlist_rules_clear_btn,
+                "None, lines are tried as they are",
+            ),
+            RuleSlot::Combinator(CombinatorSide::Left) => (
+                &imp.combinator_left_rules,
+                &imp.combinator_left_rules_row,
+                &imp.combinator_left_rules_clear_btn,
+                "None, lines are joined as they are",
+            ),
+            RuleSlot::Combinator(CombinatorSide::Right) => (
+                &imp.combinator_right_rules,
+                &imp.combinator_right_rules_row,
+                &imp.combinator_right_rules_clear_btn,
+                "None, lines are joined as they are",
+            ),
+        };
+        match &rule_file {
+            Some(rule_file) => {
+                let mut subtitle = format!("{}, {} rules", rule_file.name(), rule_file.rules.len());
+                let invalid = rule_file.rules.invalid().len();
+                if invalid > 0 {
+                    subtitle.push_str(&format!(" ({} invalid lines skipped)", invalid));
+                }
+                row.set_subtitle(&glib::markup_escape_text(&subtitle));
+                clear_btn.set_visible(true);
+            }
+            None => {
+                row.set_subtitle(unset);
+                clear_btn.set_visible(false);
+            }
+        }
+        rules.replace(rule_file);
+    }
+
+    fn import_combinator_wordlist(&self, side: CombinatorSide) {
+        let dialog = gtk::FileChooserNative::new(
+            Some("Choose Wordlist"),
+            Some(self),
+            gtk::FileChooserAction::Open,
+            Some("Choose"),
+            Some("Cancel"),
+        );
+        dialog.set_modal(true);
+
+        dialog.connect_response(clone!(@weak self as win => move |d, response| {
+            if response == gtk::ResponseType::Accept {
+                let file = &d.file().expect("Couldn't get file");
+                let path = file.path().expect("Couldn't get file path");
+                win.set_combinator_wordlist(side, Some(path));
+            }
+            d.destroy();
+        }));
+        dialog.show();
+    }
+
+    fn set_combinator_wordlist(&self, side: CombinatorSide, path: Option<PathBuf>) {
+        let imp = self.imp();
+        let (wordlist, row) = match side {
+            CombinatorSide::Left => (&imp.combinator_left, &imp.combinator_left_row),
+            CombinatorSide::Right => (&imp.combinator_right, &imp.combinator_right_row),
+        };
+        let subtitle = match &path {
+            Some(path) => SourceKind::File(path.clone()).name(),
+            None => String::from("None"),
+        };
+        row.set_subtitle(&glib::markup_escape_text(&subtitle));
+        wordlist.replace(path);
+    }
+
+    /// Identifies the queued wordlists for a checkpoint, as long as they are
+    /// all files that can be found again.
+    fn wordlist_identities(&self) -> Option<Vec<FileIdentity>> {
+        let imp = self.imp();
+        imp.wordlist_sources
+            .borrow()
+            .iter()
+            .map(|source| match &source.kind {
+                SourceKind::File(path) => FileIdentity::of(path)
+                    .map_err(|err| log::warn!("Failed to identify wordlist, {}", &err))
+                    .ok(),
+                SourceKind::Text(_) => None,
+            })
+            .collect()
+    }
+
+    /// The mask attack as entered on the mask page.
+    fn mask_spec(&self) -> MaskSpec {
+        let imp = self.imp();
+        let increment_min = imp.mask_increment_min_spin.value_as_int() as usize;
+        MaskSpec {
+            mask: imp.mask_entry.text().to_string(),
+            charsets: [
+                &imp.mask_charset1_entry,
+                &imp.mask_charset2_entry,
+                &imp.mask_charset3_entry,
+                &imp.mask_charset4_entry,
+            ]
+            .iter()
+            .map(|entry| entry.text().to_string())
+            .collect(),
+            increment: imp
+                .mask_increment_switch
+                .is_active()
+                .then_some(increment_min..=MAX_PASSPHRASE_LEN),
+        }
+    }
+
+    fn set_mask_spec(&self, spec: &MaskSpec) {
+        let imp = self.imp();
+        imp.mask_entry.set_text(&spec.mask);
+        let entries = [
+            &imp.mask_charset1_entry,
+            &imp.mask_charset2_entry,
+            &imp.mask_charset3_entry,
+            &imp.mask_charset4_entry,
+        ];
+        for (index, entry) in entries.iter().enumerate() {
+            entry.set_text(spec.charsets.get(index).map_or("", String::as_str));
+        }
+        imp.mask_increment_switch
+            .set_active(spec.increment.is_some());
+        if let Some(lengths) = &spec.increment {
+            imp.mask_increment_min_spin
+                .set_value(*lengths.start() as f64);
+        }
+    }
+
+    /// Shows how many candidates the mask makes, or what is wrong with it.
+    fn update_mask_keyspace(&self) {
+        let imp = self.imp();
+        imp.mask_entry.remove_css_class("error");
+
+        let spec = self.mask_spec();
+        let text = if spec.mask.is_empty() {
+            String::from("Enter a mask to see how many candidates it makes")
+        } else {
+            // A hybrid attack joins the mask to words, so it can be shorter
+            // than a passphrase
+            let (keyspace, skipped) = match self.hybrid_side() {
+                Some(_) => (spec.keyspace(), Vec::new()),
+                None => (
+                    spec.passphrase_keyspace(),
+                    spec.too_short_lengths().unwrap_or_default(),
+                ),
+            };
+            let mut text = match keyspace {
+                Ok(1) => String::from("Keyspace: 1 candidate"),
+                Ok(keyspace) => format!("Keyspace: {} candidates", group_digits(keyspace)),
+                Err(err) => err.to_string(),
+            };
+            if let (Some(first), Some(last)) = (skipped.first(), skipped.last()) {
+                text.push_str(&format!(
+                    "\nLengths {} to {} are skipped, passphrases are at least {} characters",
+                    first, last, MIN_PASSPHRASE_LEN
+                ));
+            }
+            text
+        };
+        imp.mask_keyspace_label.set_text(&text);
+    }
+
+    /// Every target to crack: those added on the network page, and the one
+    /// still in its entries, if any. `None` after showing why one isn't
+    /// valid.
+    fn network_targets(&self) -> Option<Vec<PmkidTarget>> {
+        let imp = self.imp();
+        let mut targets: Vec<PmkidTarget> = imp
+            .network_targets
+            .borrow()
+            .iter()
+            .map(|queued| queued.target.clone())
+            .collect();
+        if targets.is_empty() || !self.network_entries_empty() {
+            let target = self.network_target()?;
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        Some(targets)
+    }
+
+    fn network_entries_empty(&self) -> bool {
+        let imp = self.imp();
+        [
+            &imp.network_essid_entry,
+            &imp.network_bssid_entry,
+            &imp.network_sta_mac_entry,
+            &imp.network_pmkid_entry,
+        ]
+        .iter()
+        .all(|entry| entry.text().is_empty())
+    }
+
+    /// Fills the entries of the network page in with `target`, or empties
+    /// them.
+    fn set_network_entries(&self, target: Option<&PmkidTarget>) {
+        let imp = self.imp();
+        match target {
+            Some(target) => {
+                imp.network_essid_entry
+                    .set_text(&String::from_utf8_lossy(&target.essid));
+                imp.network_bssid_entry
+                    .set_text(&hex::encode(target.mac_ap));
+                imp.network_sta_mac_entry
+                    .set_text(&hex::encode(target.mac_sta));
+                imp.network_pmkid_entry.set_text(&hex::encode(target.pmkid));
+            }
+            None => {
+                imp.network_essid_entry.set_text("");
+                imp.network_bssid_entry.set_text("");
+                imp.network_sta_mac_entry.set_text("");
+                imp.network_pmkid_entry.set_text("");
+            }
+        }
+    }
+
+    /// Adds `target` to those cracked together, unless it is there already.
+    fn add_network_target(&self, target: PmkidTarget) {
+        let imp = self.imp();
+        if imp
+            .network_targets
+            .borrow()
+            .iter()
+            .any(|queued| queued.target == target)
+        {
+            return;
+        }
+
+        let row = adw::ActionRow::new();
+        row.set_title(&glib::markup_escape_text(&String::from_utf8_lossy(
+            &target.essid,
+        )));
+        row.set_subtitle(&format!(
+            "{} · {}",
+            format_mac(&target.mac_ap),
+            hex::encode(target.pmkid)
+        ));
+
+        let remove_btn = gtk::Button::from_icon_name("user-trash-symbolic");
+        remove_btn.set_tooltip_text(Some("Remove"));
+        remove_btn.set_valign(gtk::Align::Center);
+        remove_btn.add_css_class("flat");
+        remove_btn.connect_clicked(clone!(@weak self as win, @weak row => move |_| {
+            win.remove_network_target(&row);
+        }));
+        row.add_suffix(&remove_btn);
+
+        imp.network_targets_list.append(&row);
+        imp.network_targets
+            .borrow_mut()
+            .push(QueuedTarget { target, row });
+        imp.network_targets_box.set_visible(true);
+    }
+
+    fn remove_network_target(&self, row: &adw::ActionRow) {
+        let imp = self.imp();
+        let mut targets = imp.network_targets.borrow_mut();
+        targets.retain(|queued| queued.row != *row);
+        imp.network_targets_list.remove(row);
+        imp.network_targets_box.set_visible(!targets.is_empty());
+    }
+
+    fn clear_network_targets(&self) {
+        let imp = self.imp();
+        for queued in imp.network_targets.take() {
+            imp.network_targets_list.remove(&queued.row);
+        }
+        imp.network_targets_box.set_visible(false);
+    }
+
+    /// The target entered on the network page, or `None` after showing why
+    /// it isn't valid.
+    fn network_target(&self) -> Option<PmkidTarget> {
+        let imp = self.imp();
+        match PmkidTarget::from_strings(
+            &imp.network_essid_entry.text(),
+            &imp.network_bssid_entry.text(),
+            &imp.network_sta_mac_entry.text(),
+            &imp.network_pmkid_entry.text(),
+        ) {
+            Ok(target) => Some(target),
+            Err(err) => {
+                self.show_error(&err.to_string());
+                None
+            }
+        }
+    }
+
+    fn complete_mask_process(&self) {
+        let imp = self.imp();
+
+        let spec = self.mask_spec();
+        if spec.passphrase_keyspace().is_err() {
+            imp.mask_entry.add_css_class("error");
+            return;
+        }
+
+        let targets = match self.network_targets() {
+            Some(targets) => targets,
+            None => return,
+        };
+        let checkpoint = Checkpoint::new(targets.clone(), Attack::Mask { mask: spec.clone() });
+
+        self.page_switch("cracking_page");
+        self.start_mask_attack(targets, &spec, Some(checkpoint));
+    }
+
+    fn complete_wordlist_process(&self) {
+        let imp = self.imp();
+
+        // Anything still in the text view is tried last
+        self.add_wordlist_text();
+        if imp.wordlist_sources.borrow().is_empty() {
+            imp.wordlist_text.add_css_class("error");
+            return;
+        }
+
+        let hybrid = self.hybrid_side().map(|side| (self.mask_spec(), side));
+        if let Some((spec, _)) = &hybrid {
+            if spec.keyspace().is_err() {
+                imp.attack_stack.set_visible_child_name("mask");
+                imp.mask_entry.add_css_class("error");
+                return;
+            }
+        }
+
+        let targets = match self.network_targets() {
+            Some(targets) => targets,
+            None => return,
+        };
+
+        if let Some((spec, side)) = hybrid {
+            let checkpoint = self.wordlist_identities().map(|wordlists| {
+                Checkpoint::new(
+                    targets.clone(),
+                    Attack::Hybrid {
+                        wordlists,
+                        mask: spec.clone(),
+                        side,
+                    },
+                )
+            });
+            self.page_switch("cracking_page");
+            self.start_hybrid_attack(targets, &spec, side, checkpoint);
+            return;
+        }
+
+        // Only files can be found again to resume from
+        let checkpoint = self.wordlist_identities().and_then(|wordlists| {
+            let rules = match imp.wordlist_rules.borrow().as_ref() {
+                Some(rule_file) => Some(
+                    FileIdentity::of(&rule_file.path)
+                        .map_err(|err| log::warn!("Failed to identify rule file, {}", &err))
+                        .ok()?,
+                ),
+                None => None,
+            };
+            Some(Checkpoint::new(
+                targets.clone(),
+                Attack::Wordlist { wordlists, rules },
+            ))
+        });
+
+        self.page_switch("cracking_page");
+        self.start_wordlist_attack(targets, checkpoint);
+    }
+
+    fn complete_combinator_process(&self) {
+        let imp = self.imp();
+
+        let left = imp.combinator_left.borrow().clone();
+        let right = imp.combinator_right.borrow().clone();
+        let (left, right) = match (left, right) {
+            (Some(left), Some(right)) => (left, right),
+            (left, right) => {
+                if left.is_none() {
+                    imp.combinator_left_row.add_css_class("error");
+                }
+                if right.is_none() {
+                    imp.combinator_right_row.add_css_class("error");
+                }
+                return;
+            }
+        };
+
+        let targets = match self.network_targets() {
+            Some(targets) => targets,
+            None => return,
+        };
+
+        let identify = |path: &Path| {
+            FileIdentity::of(path)
+                .map_err(|err| log::warn!("Failed to identify {}, {}", path.display(), &err))
+                .ok()
+        };
+        let identify_rules = |rules: &RefCell<Option<RuleFile>>| match rules.borrow().as_ref() {
+            Some(rule_file) => identify(&rule_file.path).map(Some),
+            None => Some(None),
+        };
+        // Only files that can be found again can be resumed from
+        let checkpoint = match (
+            identify(&left),
+            identify(&right),
+            identify_rules(&imp.combinator_left_rules),
+            identify_rules(&imp.combinator_right_rules),
+        ) {
+            (Some(left), Some(right), Some(left_rules), Some(right_rules)) => {
+                Some(Checkpoint::new(
+                    targets.clone(),
+                    Attack::Combinator {
+                        left,
+                        right,
+                        separator: imp.combinator_separator_entry.text().to_string(),
+                        left_rules,
+                        right_rules,
+                    },
+                ))
+            }
+            _ => None,
+        };
+
+        self.page_switch("cracking_page");
+        self.start_combinator_attack(targets, checkpoint);
+    }
+
+    /// Measures the throughput of this machine in the background, showing
+    /// the result in a dialog and saving it for later estimates.
+    pub fn show_benchmark(&self) {
+        let dialog = adw::MessageDialog::new(
+            Some(self),
+            Some("Benchmark"),
+            Some("Measuring how many PMKs per second this machine derives…"),
+        );
+        dialog.add_response("close", "Close");
+        dialog.present();
+
+        let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+        let backend = Pbkdf2Backend::detect();
+        thread::spawn(move || {
+            let _ = sender.send(Benchmark::run(BENCHMARK_STEP, backend));
+        });
+        receiver.attach(
+            None,
+            clone!(@weak dialog => @default-return glib::Continue(false), move |benchmark| {
+                let benchmark = match benchmark {
+                    Ok(benchmark) => benchmark,
+                    Err(err) => {
+                        dialog.set_body(&format!("Couldn't run the benchmark: {}", err));
+                        return glib::Continue(false);
+                    }
+                };
+                if let Err(err) = benchmark.save(&engine::benchmark_path()) {
+                    log::warn!("Failed to save benchmark, {}", &err);
+                }
+
+                let mut body = format!(
+                    "{} PMK/s at best, using {}",
+                    group_digits(benchmark.rate() as u64),
+                    backend
+                );
+                for point in &benchmark.points {
+                    body.push_str(&format!(
+                        "\n{} {}: {} PMK/s, {} per thread",
+                        point.threads,
+                        if point.threads == 1 { "thread" } else { "threads" },
+                        group_digits(point.rate as u64),
+                        group_digits(point.rate_per_thread() as u64)
+                    ));
+                }
+                dialog.set_body(&body);
+                glib::Continue(false)
+            }),
+        );
+    }
+
+    fn load_history(&self) {
+        match History::load(history_path()) {
+            Ok(history) => {
+                self.imp().history.replace(Some(history));
+            }
+            Err(err) => log::warn!("Failed to read history, {}", &err),
+        }
+    }
+
+    fn show_history(&self) {
+        let imp = self.imp();
+        let page = imp.main_stack.visible_child_name();
+        if let Some(page) = page.filter(|page| page != "history_page") {
+            imp.history_return.replace(page.to_string());
+        }
+        self.refresh_history();
+        self.page_switch("history_page");
+    }
+
+    /// Fills the history page with the entries matching the search, in the
+    /// chosen order.
+    fn refresh_history(&self) {
+        let imp = self.imp();
+        while let Some(row) = imp.history_list.row_at_index(0) {
+            imp.history_list.remove(&row);
+        }
+
+        let history = imp.history.borrow();
+        let entries = history.as_ref().map_or(&[][..], History::entries);
+        let query = imp.history_search_entry.text().to_lowercase();
+        let mut entries: Vec<&HistoryEntry> = entries
+            .iter()
+            .filter(|entry| query.is_empty() || history_text(entry).contains(&query))
+            .collect();
+        match imp.history_sort_dropdown.selected() {
+            1 => entries.sort_by_key(|entry| (entry.started, entry.id)),
+            2 => entries.sort_by(|a, b| {
+                a.target
+                    .essid
+                    .cmp(&b.target.essid)
+                    .then(b.started.cmp(&a.started))
+            }),
+            3 => entries.sort_by_key(|entry| Reverse(entry.duration)),
+            _ => entries.sort_by_key(|entry| Reverse((entry.started, entry.id))),
+        }
+
+        imp.history_placeholder.set_label(if query.is_empty() {
+            "Every crack shows up here once it is over"
+        } else {
+            "Nothing matches the search"
+        });
+        for entry in entries {
+            imp.history_list.append(&self.history_row(entry));
+        }
+    }
+
+    fn history_row(&self, entry: &HistoryEntry) -> adw::ExpanderRow {
+        let started = glib::DateTime::from_unix_local(entry.started as i64)
+            .and_then(|date| date.format("%c"))
+            .map(String::from)
+            .unwrap_or_default();
+        let row = adw::ExpanderRow::builder()
+            .title(&glib::markup_escape_text(&String::from_utf8_lossy(
+                &entry.target.essid,
+            )))
+            .subtitle(&format!(
+                "{} · {}",
+                format_mac(&entry.target.mac_ap),
+                started
+            ))
+            .build();
+
+        let (status, outcome) = match &entry.outcome {
+            HistoryOutcome::Found { passphrase } => {
+                ("Found", format!("Found, the passphrase is {}", passphrase))
+            }
+            HistoryOutcome::Exhausted => ("Not Found", String::from("No match found")),
+            HistoryOutcome::Cancelled => ("Cancelled", String::from("Cancelled")),
+            HistoryOutcome::Failed { error } => ("Failed", error.clone()),
+        };
+        let status = gtk::Label::new(Some(status));
+        status.add_css_class("dim-label");
+        row.add_action(&status);
+
+        let rules = if entry.rules.is_empty() {
+            String::from("None")
+        } else {
+            entry.rules.join(", ")
+        };
+        for (title, subtitle) in [
+            ("Outcome", outcome),
+            ("Attack", entry.attack.clone()),
+            ("Sources", entry.sources.join(", ")),
+            ("Rules", rules),
+            ("Duration", format_duration(entry.duration)),
+            ("Candidates Tried", group_digits(entry.tried)),
+        ] {
+            let detail = adw::ActionRow::builder()
+                .title(title)
+                .subtitle(&glib::markup_escape_text(&subtitle))
+                .build();
+            row.add_row(&detail);
+        }
+
+        let rerun_btn = gtk::Button::with_label("Run Again With Another Wordlist");
+        rerun_btn.add_css_class("pill");
+        let delete_btn = gtk::Button::with_label("Delete");
+        delete_btn.add_css_class("pill");
+        delete_btn.add_css_class("destructive-action");
+        let actions = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(12)
+            .halign(gtk::Align::End)
+            .margin_start(12)
+            .margin_end(12)
+            .margin_top(12)
+            .margin_bottom(12)
+            .build();
+        actions.append(&rerun_btn);
+        actions.append(&delete_btn);
+        row.add_row(&actions);
+
+        let target = entry.target.clone();
+        rerun_btn.connect_clicked(clone!(@weak self as win => move |_| {
+            win.rerun_with_another_wordlist(&target);
+        }));
+        let id = entry.id;
+        delete_btn.connect_clicked(clone!(@weak self as win => move |_| {
+            win.delete_history_entry(id);
+        }));
+
+        row
+    }
+
+    /// Fills in the network of a past crack and leaves the wordlists to be
+    /// picked again.
+    fn rerun_with_another_wordlist(&self, target: &PmkidTarget) {
+        let imp = self.imp();
+        self.clear_network_targets();
+        self.set_network_entries(Some(target));
+
+        imp.wordlist_text.buffer().set_text("");
+        self.clear_wordlist_sources();
+        self.set_hybrid_side(None);
+        imp.attack_stack.set_visible_child_name("wordlist");
+        self.page_switch("wordlist_page");
+    }
+
+    fn delete_history_entry(&self, id: u64) {
+        let imp = self.imp();
+        if let Some(history) = imp.history.borrow_mut().as_mut() {
+            if let Err(err) = history.remove(id) {
+                log::warn!("Failed to write history, {}", &err);
+            }
+        }
+        self.refresh_history();
+    }
+
+    /// The start of the history entry of a crack about to be run against
+    /// `target` over the sources called `names`, as set up on the wordlist
+    /// page.
+    fn history_entry(&self, target: &PmkidTarget, names: &[String]) -> HistoryEntry {
+        let imp = self.imp();
+        let mask = imp.mask_entry.text();
+        let rule_names = |slots: &[&RefCell<Option<RuleFile>>]| -> Vec<String> {
+            slots
+                .iter()
+                .filter_map(|slot| slot.borrow().as_ref().map(RuleFile::name))
+                .collect()
+        };
+        let (attack, rules) = match imp.attack_stack.visible_child_name().as_deref() {
+            Some("mask") => (format!("Mask {}", mask), Vec::new()),
+            Some("combinator") => (
+                String::from("Combinator"),
+                rule_names(&[&imp.combinator_left_rules, &imp.combinator_right_rules]),
+            ),
+            _ => match self.hybrid_side() {
+                Some(HybridSide::Append) => (format!("Wordlist + Mask {}", mask), Vec::new()),
+                Some(HybridSide::Prepend) => (format!("Mask {} + Wordlist", mask), Vec::new()),
+                None => (String::from("Wordlist"), rule_names(&[&imp.wordlist_rules])),
+            },
+        };
+
+        HistoryEntry {
+            id: 0,
+            target: target.clone(),
+            started: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+            duration: Duration::ZERO,
+            tried: 0,
+            attack,
+            sources: names.to_vec(),
+            rules,
+            outcome: HistoryOutcome::Cancelled,
+        }
+    }
+
+    /// Finishes `entries`, one per target of a crack, with how it ended for
+    /// each and adds them to the history.
+    fn record_history(&self, entries: Vec<HistoryEntry>, outcome: &io::Result<Outcome>) {
+        let imp = self.imp();
+        let mut history = imp.history.borrow_mut();
+        let history = match history.as_mut() {
+            Some(history) => history,
+            None => return,
+        };
+
+        for (index, mut entry) in entries.into_iter().enumerate() {
+            let (duration, tried, target_outcome) = match outcome {
+                Ok(outcome) => {
+                    let (elapsed, tried, unsolved) = match outcome {
+                        Outcome::Found { elapsed, tried, .. }
+                        | Outcome::Exhausted { elapsed, tried, .. } => {
+                            (*elapsed, *tried, HistoryOutcome::Exhausted)
+                        }
+                        Outcome::Cancelled { elapsed, tried, .. } => {
+                            (*elapsed, *tried, HistoryOutcome::Cancelled)
+                        }
+                    };
+                    let target_outcome = match outcome.passphrase(index) {
+                        Some(passphrase) => HistoryOutcome::Found {
+                            passphrase: format_passphrase(passphrase),
+                        },
+                        None => unsolved,
+                    };
+                    (elapsed, tried, target_outcome)
+                }
+                Err(err) => (
+                    Duration::ZERO,
+                    0,
+                    HistoryOutcome::Failed {
+                        error: err.to_string(),
+                    },
+                ),
+            };
+            entry.duration = duration;
+            entry.tried = tried;
+            entry.outcome = target_outcome;
+            if let Err(err) = history.push(entry) {
+                log::warn!("Failed to write history, {}", &err);
+            }
+        }
+    }
+
+    fn save_window_size(&self) -> Result<(), glib::BoolError> {
+        let imp = self.imp();
+
+        let (width, height) = self.default_size();
 
         imp.settings.set_int("window-width", width)?;
         imp.settings.set_int("window-height", height)?;
@@ -410,116 +2183,689 @@ impl RamshApplicationWindow {
         }
     }
 
-    fn get_result(
+    /// How the wordlists are combined with the mask, if they are.
+    fn hybrid_side(&self) -> Option<HybridSide> {
+        match self.imp().wordlist_mask_row.selected() {
+            1 => Some(HybridSide::Append),
+            2 => Some(HybridSide::Prepend),
+            _ => None,
+        }
+    }
+
+    fn set_hybrid_side(&self, side: Option<HybridSide>) {
+        let selected = match side {
+            None => 0,
+            Some(HybridSide::Append) => 1,
+            Some(HybridSide::Prepend) => 2,
+        };
+        self.imp().wordlist_mask_row.set_selected(selected);
+    }
+
+    /// Opens every queued wordlist, in order, or shows which one couldn't be.
+    fn open_wordlist_sources(&self) -> Option<Vec<Wordlist>> {
+        let imp = self.imp();
+        let mut wordlists = Vec::new();
+        for source in imp.wordlist_sources.borrow().iter() {
+            match source.kind.open() {
+                Ok(wordlist) => wordlists.push(wordlist),
+                Err(err) => {
+                    self.show_error(&format!("{}: {}", source.kind.name(), err));
+                    return None;
+                }
+            }
+        }
+        Some(wordlists)
+    }
+
+    /// The line counts of the queued wordlists, in order. Those not counted
+    /// yet are opened to be counted, or it is shown which one couldn't be.
+    fn wordlist_source_lines(&self) -> Option<Vec<LineCount>> {
+        let imp = self.imp();
+        let mut counts = Vec::new();
+        for source in imp.wordlist_sources.borrow().iter() {
+            if let Some(lines) = source.lines {
+                counts.push(LineCount::Known(lines));
+                continue;
+            }
+            match source.kind.open() {
+                Ok(wordlist) => counts.push(LineCount::Pending(wordlist)),
+                Err(err) => {
+                    self.show_error(&format!("{}: {}", source.kind.name(), err));
+                    return None;
+                }
+            }
+        }
+        Some(counts)
+    }
+
+    fn wordlist_source_names(&self) -> Vec<String> {
+        let imp = self.imp();
+        imp.wordlist_sources
+            .borrow()
+            .iter()
+            .map(|source| source.kind.name())
+            .collect()
+    }
+
+    /// Starts trying the queued wordlists against `targets`, in order.
+    fn start_wordlist_attack(&self, targets: Vec<PmkidTarget>, checkpoint: Option<Checkpoint>) {
+        let imp = self.imp();
+
+        // Wordlists are streamed by the engine, never loaded into the UI
+        let wordlists = match self.open_wordlist_sources() {
+            Some(wordlists) => wordlists,
+            None => return,
+        };
+        let rules = imp
+            .wordlist_rules
+            .borrow()
+            .as_ref()
+            .map(|rule_file| rule_file.rules.clone());
+
+        // With rules, progress counts candidates, so the lines of the
+        // wordlists are needed, counting again those that weren't already
+        let counted = match &rules {
+            Some(_) => match self.wordlist_source_lines() {
+                Some(counted) => Some(counted),
+                None => return,
+            },
+            None => None,
+        };
+
+        imp.cracking_skip_btn.set_label("Skip Wordlist");
+        let counts_candidates = rules.is_some();
+        self.start_cracking(
+            targets,
+            self.wordlist_source_names(),
+            counts_candidates,
+            move || {
+                let mut queue = WordlistQueue::new();
+                match (rules, counted) {
+                    (Some(rules), Some(counted)) => {
+                        for (wordlist, counted) in wordlists.into_iter().zip(counted) {
+                            let words = counted.get()?;
+                            queue.push(Mangled::new(wordlist, rules.clone(), words));
+                        }
+                    }
+                    _ => wordlists
+                        .into_iter()
+                        .for_each(|wordlist| queue.push(wordlist)),
+                }
+                Ok(queue)
+            },
+            checkpoint,
+        );
+    }
+
+    /// Starts trying the candidates of the mask against `targets`, one mask
+    /// length after the other when incrementing.
+    fn start_mask_attack(
+        &self,
+        targets: Vec<PmkidTarget>,
+        spec: &MaskSpec,
+        checkpoint: Option<Checkpoint>,
+    ) {
+        let imp = self.imp();
+
+        let masks = match spec.passphrase_masks() {
+            Ok(masks) => masks,
+            Err(err) => {
+                self.show_error(&format!("{}: {}", spec.mask, err));
+                return;
+            }
+        };
+
+        // Candidates are generated by the engine as it goes
+        let mut queue = WordlistQueue::new();
+        let mut names = Vec::new();
+        for mask in masks {
+            names.push(format!("Length {}", mask.len()));
+            queue.push(MaskCandidates::new(mask));
+        }
+
+        imp.cracking_skip_btn.set_label("Skip Length");
+        self.start_cracking(targets, names, true, move || Ok(queue), checkpoint);
+    }
+
+    /// Starts trying every line of the queued wordlists combined with every
+    /// candidate of the mask, one mask length after the other when
+    /// incrementing.
+    fn start_hybrid_attack(
         &self,
-        essid: String,
-        bssid: String,
-        sta_mac: String,
-        pmkid: String,
-        wordlist_dict: Vec<String>,
-    ) -> Option<&str> {
-        let (sender_state, receiver_state) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
-        let (sender_pass, receiver_pass) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
-        let (sender_progress, receiver_progress) =
-            glib::MainContext::channel(glib::PRIORITY_DEFAULT);
-
-        let essid = essid.as_bytes().to_owned();
-
-        let bssid = bssid
-            .to_lowercase()
-            .replace(":", "")
-            .replace("-", "")
-            .replace(".", "");
-
-        let sta_mac = sta_mac
-            .to_lowercase()
-            .replace(":", "")
-            .replace("-", "")
-            .replace(".", "");
-
-        let pmkid_data = pmkid
-            .split("*")
-            .map(str::to_string)
-            .collect::<Vec<String>>();
-
-        let pmkid_hash = &*pmkid_data.get(0).unwrap();
-        let pmkid_hash = pmkid_hash.clone();
-
-        /*
-            PMKID = HMAC-SHA1-128(PMK, "PMK Name" | MAC_AP | MAC_STA)
-            where the PMK is the pbkdf2_hmac of passphrase.
-            params -> ("PMK Name" | MAC_AP | MAC_STA)
-        */
-        let params = [
-            b"PMK Name",
-            hex::decode(&bssid).unwrap().as_bytes(),
-            hex::decode(&sta_mac).unwrap().as_bytes(),
-        ]
-        .concat();
+        targets: Vec<PmkidTarget>,
+        spec: &MaskSpec,
+        side: HybridSide,
+        checkpoint: Option<Checkpoint>,
+    ) {
+        let imp = self.imp();
 
-        let total_crack_time = Instant::now();
+        let masks = match spec.masks() {
+            Ok(masks) => masks,
+            Err(err) => {
+                self.show_error(&format!("{}: {}", spec.mask, err));
+                return;
+            }
+        };
 
-        thread::spawn(move || {
-            wordlist_dict.par_iter().for_each(|passphrase| {
-                // returns the hash generated using the passphrase
-                // compare the both pmkids and validate
-                let _ = sender_progress.send(1.0 / wordlist_dict.len() as f64);
-
-                /*
-                    derive the pbkdf2 using the network name and passphrase
-                    this is usually the most time consuming part
-                */
-                let mut key_out = [0u8; 32];
-                pbkdf2_hmac(passphrase.as_bytes(), &essid, 4096, &mut key_out);
-
-                /*
-                    get the hmac-sha1 of the param using the pmk as key
-                    and get the first 32 bits from its hexdigest
-                */
-                let hash = hmacsha1::hmac_sha1(&key_out, &params.clone().as_bytes()).to_hex();
-                let pmkid = hash.get(..32);
-
-                let new_hash = pmkid.unwrap().to_string();
-                if new_hash == pmkid_hash {
-                    let _ = sender_pass.send(String::from(format!(
-                        "PMKID Hash: {}\n\nPassphrase: <b>{}</b>\n\nTime Taken: {} ms",
-                        pmkid_hash,
-                        passphrase,
-                        total_crack_time.elapsed().as_millis()
-                    )));
+        // A pass over the wordlists whose lines aren't counted yet, then one
+        // per mask length. They are all opened here, so that a missing file
+        // is reported by name.
+        let counted = match self.wordlist_source_lines() {
+            Some(wordlists) => wordlists,
+            None => return,
+        };
+        let source_names = self.wordlist_source_names();
+        let mut passes = Vec::new();
+        let mut names = Vec::new();
+        for mask in &masks {
+            let wordlists = match self.open_wordlist_sources() {
+                Some(wordlists) => wordlists,
+                None => return,
+            };
+            passes.push((mask.clone(), wordlists));
+            for name in &source_names {
+                names.push(if masks.len() > 1 {
+                    format!("{}, mask length {}", name, mask.len())
+                } else {
+                    name.clone()
+                });
+            }
+        }
+
+        imp.cracking_skip_btn.set_label("Skip Wordlist");
+        self.start_cracking(
+            targets,
+            names,
+            true,
+            move || {
+                let words = counted
+                    .into_iter()
+                    .map(LineCount::get)
+                    .collect::<io::Result<Vec<u64>>>()?;
+
+                let mut queue = WordlistQueue::new();
+                for (mask, wordlists) in passes {
+                    for (wordlist, &words) in wordlists.into_iter().zip(&words) {
+                        queue.push(Hybrid::new(wordlist, mask.clone(), side, words));
+                    }
+                }
+                Ok(queue)
+            },
+            checkpoint,
+        );
+    }
+
+    /// Starts trying every line of the left wordlist joined with every line
+    /// of the right one.
+    fn start_combinator_attack(&self, targets: Vec<PmkidTarget>, checkpoint: Option<Checkpoint>) {
+        let imp = self.imp();
+
+        let (left_path, right_path) = match (
+            imp.combinator_left.borrow().clone(),
+            imp.combinator_right.borrow().clone(),
+        ) {
+            (Some(left), Some(right)) => (left, right),
+            _ => return,
+        };
+        let left_name = SourceKind::File(left_path.clone()).name();
+        let right_name = SourceKind::File(right_path.clone()).name();
+
+        // The left wordlist is read twice, once to count its lines. They are
+        // all opened here, so that a missing file is reported by name.
+        let mut wordlists = Vec::new();
+        for path in [&left_path, &left_path, &right_path] {
+            match Wordlist::open(path) {
+                Ok(wordlist) => wordlists.push(wordlist),
+                Err(err) => {
+                    self.show_error(&format!("{}: {}", path.display(), err));
+                    return;
+                }
+            }
+        }
+        let mut wordlists = wordlists.into_iter();
+        let (mut counted, left, right) = (
+            wordlists.next().unwrap(),
+            wordlists.next().unwrap(),
+            wordlists.next().unwrap(),
+        );
+
+        let rules = |rules: &RefCell<Option<RuleFile>>| {
+            rules
+                .borrow()
+                .as_ref()
+                .map(|rule_file| rule_file.rules.clone())
+        };
+        let left_rules = rules(&imp.combinator_left_rules);
+        let right_rules = rules(&imp.combinator_right_rules);
+        let separator = imp.combinator_separator_entry.text().as_bytes().to_vec();
+
+        imp.cracking_skip_btn.set_label("Skip Wordlist");
+        self.start_cracking(
+            targets,
+            vec![format!("{} + {}", left_name, right_name)],
+            true,
+            move || {
+                let right = match right_rules {
+                    Some(rules) => collect_candidates(Mangled::new(right, rules, 0))?,
+                    None => collect_candidates(right)?,
                 };
-            });
-            let _ = sender_state.send(String::from("No match found"));
-        });
+                if right.is_empty() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{} has no candidates", right_name),
+                    ));
+                }
 
+                let mut queue = WordlistQueue::new();
+                match left_rules {
+                    Some(rules) => {
+                        let words = count_candidates(Mangled::new(counted, rules.clone(), 0))?;
+                        let left = Mangled::new(left, rules, 0);
+                        queue.push(Combinator::new(left, words, right, separator));
+                    }
+                    None => {
+                        let words = counted.count_lines()?;
+                        queue.push(Combinator::new(left, words, right, separator));
+                    }
+                }
+                Ok(queue)
+            },
+            checkpoint,
+        );
+    }
+
+    /// Runs the engine in the background over the queue `build` makes, with
+    /// the cracking page following along.
+    ///
+    /// `build` runs on the engine's thread, so it can take its time. `names`
+    /// are those of the sources it queues, for the per-source progress.
+    /// `targets` are all cracked at once, each leaving the run as it is
+    /// found. `counts_candidates` is whether the progress of the queue is in
+    /// candidates, as for every attack but plain wordlists, whose progress is
+    /// in bytes.
+    fn start_cracking<F>(
+        &self,
+        targets: Vec<PmkidTarget>,
+        names: Vec<String>,
+        counts_candidates: bool,
+        build: F,
+        checkpoint: Option<Checkpoint>,
+    ) where
+        F: FnOnce() -> io::Result<WordlistQueue> + Send + 'static,
+    {
+        self.cancel_cracking();
         let imp = self.imp();
 
-        let cracking_progress_clone = imp.cracking_progress.clone();
-        receiver_progress.attach(None, move |msg| {
-            cracking_progress_clone.set_fraction(cracking_progress_clone.fraction() + msg);
-            glib::Continue(true)
-        });
+        let mut cracker = match Cracker::with_targets(targets.clone()) {
+            Ok(cracker) => cracker,
+            Err(err) => {
+                self.show_error(&err.to_string());
+                return;
+            }
+        };
 
-        let main_stack_clone = imp.main_stack.clone();
-        let success_status_page_clone = imp.success_status_page.clone();
-        receiver_pass.attach(None, move |msg| {
-            success_status_page_clone.set_description(Some(msg.as_str()));
-            main_stack_clone.set_visible_child_name("success_page");
-            glib::Continue(true)
+        let (sender_outcome, receiver_outcome) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+        // The total of each queued source, sent once the queue is built
+        let (sender_totals, receiver_totals) = mpsc::channel();
+
+        let entries: Vec<HistoryEntry> = targets
+            .iter()
+            .map(|target| self.history_entry(target, &names))
+            .collect();
+        let rules = entries[0].rules.clone();
+        let mut history_entries = Some(entries);
+
+        let target_labels = self.show_cracking_targets(&targets);
+        let filter = CandidateFilter {
+            dedupe: imp.settings.boolean("dedupe-candidates"),
+            raw_psk: imp.settings.boolean("accept-raw-psk"),
+        };
+        let checkpointer = checkpoint.map(|mut checkpoint| {
+            checkpoint.filter = filter;
+            Arc::new(Checkpointer::new(
+                session_path(),
+                checkpoint,
+                CHECKPOINT_INTERVAL,
+            ))
         });
+        if let Some(checkpointer) = &checkpointer {
+            cracker.set_checkpointer(checkpointer.clone());
+        }
+        cracker.set_filter(filter);
 
-        let main_stack_clone = imp.main_stack.clone();
-        let cracking_progress_clone = imp.cracking_progress.clone();
-        let failure_status_page_clone = imp.failure_status_page.clone();
-        receiver_state.attach(None, move |msg| {
-            failure_status_page_clone.set_description(Some(msg.as_str()));
-            if main_stack_clone.visible_child_name().unwrap() != "success_page" {
-                main_stack_clone.set_visible_child_name("failure_page");
+        let cancel_token = cracker.cancel_token();
+        let pause_token = cracker.pause_token();
+        let skip_token = cracker.skip_token();
+        let status = cracker.status_handle();
+        thread::spawn(move || {
+            match Potfile::load(engine::potfile_path()) {
+                Ok(potfile) => {
+                    if potfile.skipped() > 0 {
+                        log::info!(
+                            "Skipped {} lines of the potfile that aren't PMKID targets",
+                            potfile.skipped()
+                        );
+                    }
+                    cracker.set_potfile(Arc::new(potfile));
+                }
+                Err(err) => log::warn!("Failed to read potfile, {}", &err),
             }
-            cracking_progress_clone.set_fraction(0.0);
-            glib::Continue(true)
+
+            // No need to open anything for targets that are already solved
+            let outcome = match cracker.recall() {
+                Some(outcome) => Ok(outcome),
+                None => build().and_then(|queue| {
+                    let _ = sender_totals.send(queue.totals());
+                    cracker.run(queue)
+                }),
+            };
+            let _ = sender_outcome.send(outcome);
         });
 
-        return None;
+        imp.crack_job.replace(Some(CrackJob {
+            cancel_token: cancel_token.clone(),
+            pause_token,
+            skip_token,
+            checkpointer,
+            current_source: Cell::new(0),
+        }));
+
+        // Only worth showing when there is more than one source
+        imp.cracking_source_box.set_visible(names.len() > 1);
+        imp.cracking_skip_btn.set_visible(names.len() > 1);
+        imp.cracking_rules_row.set_visible(!rules.is_empty());
+        imp.cracking_rules_label.set_text(&rules.join(", "));
+
+        // Poll the engine rather than have it message us, so that the main
+        // loop does the same little work however fast candidates go by
+        let cancel_token_clone = cancel_token.clone();
+        let mut current = None;
+        let mut totals: Vec<u64> = Vec::new();
+        let mut starts: Vec<u64> = Vec::new();
+        let status_source = glib::timeout_add_local(
+            STATUS_INTERVAL,
+            clone!(@weak self as win => @default-return glib::Continue(false), move || {
+                if cancel_token_clone.is_cancelled() {
+                    return glib::Continue(false);
+                }
+                // Nothing to show until the queue is built
+                if totals.is_empty() {
+                    match receiver_totals.try_recv() {
+                        Ok(source_totals) => {
+                            starts = source_totals
+                                .iter()
+                                .scan(0, |sum, total| {
+                                    let start = *sum;
+                                    *sum += total;
+                                    Some(start)
+                                })
+                                .collect();
+                            totals = source_totals;
+                        }
+                        Err(_) => return glib::Continue(true),
+                    }
+                }
+
+                let imp = win.imp();
+                let status = status.snapshot();
+                let source = status.source;
+                if current != Some(source) {
+                    current = Some(source);
+                    if let Some(job) = imp.crack_job.borrow().as_ref() {
+                        job.current_source.set(source);
+                    }
+                    imp.cracking_source_label.set_text(&format!(
+                        "{} ({} of {})",
+                        names[source],
+                        source + 1,
+                        names.len()
+                    ));
+                }
+
+                imp.cracking_progress
+                    .set_fraction(status.done as f64 / status.total.max(1) as f64);
+                let source_total = totals[source];
+                let source_done = status.done.saturating_sub(starts[source]).min(source_total);
+                imp.cracking_source_progress
+                    .set_fraction(source_done as f64 / source_total.max(1) as f64);
+
+                win.show_cracking_status(&status, counts_candidates);
+                for (label, found) in target_labels.iter().zip(&status.found) {
+                    if let Some(passphrase) = found {
+                        label.set_text(&format_passphrase(passphrase));
+                    }
+                }
+                glib::Continue(true)
+            }),
+        );
+        let mut status_source = Some(status_source);
+
+        receiver_outcome.attach(
+            None,
+            clone!(@weak self as win => @default-return glib::Continue(false), move |outcome| {
+                // Cancelled runs are recorded too
+                if let Some(entries) = history_entries.take() {
+                    win.record_history(entries, &outcome);
+                }
+                // A cancelled run's poll stops on its own
+                if cancel_token.is_cancelled() {
+                    return glib::Continue(false);
+                }
+                if let Some(status_source) = status_source.take() {
+                    status_source.remove();
+                }
+                win.show_outcome(&targets, outcome);
+                glib::Continue(false)
+            }),
+        );
+    }
+
+    /// Fills in the status panel of the cracking page. `counts_candidates`
+    /// is whether the progress of the attack is in candidates rather than
+    /// bytes.
+    fn show_cracking_status(&self, status: &Status, counts_candidates: bool) {
+        let imp = self.imp();
+        imp.cracking_tried_label.set_text(&if counts_candidates {
+            format!(
+                "{} of {}",
+                group_digits(status.done),
+                group_digits(status.total)
+            )
+        } else {
+            group_digits(status.tried)
+        });
+        imp.cracking_speed_label
+            .set_text(&format!("{} H/s", group_digits(status.rate.round() as u64)));
+        imp.cracking_elapsed_label
+            .set_text(&format_duration(status.elapsed));
+        imp.cracking_eta_label.set_text(
+            &status
+                .eta
+                .map_or_else(|| String::from("Estimating..."), format_duration),
+        );
+        imp.cracking_candidate_label.set_text(
+            &status
+                .candidate
+                .as_deref()
+                .map_or_else(String::new, format_passphrase),
+        );
+    }
+
+    /// Lists `targets` on the cracking page, if there is more than one,
+    /// returning the labels their passphrases go in once found.
+    fn show_cracking_targets(&self, targets: &[PmkidTarget]) -> Vec<gtk::Label> {
+        let imp = self.imp();
+        while let Some(row) = imp.cracking_targets_list.row_at_index(0) {
+            imp.cracking_targets_list.remove(&row);
+        }
+        imp.cracking_targets_clamp.set_visible(targets.len() > 1);
+        if targets.len() < 2 {
+            return Vec::new();
+        }
+
+        targets
+            .iter()
+            .map(|target| {
+                let row = adw::ActionRow::builder()
+                    .title(&glib::markup_escape_text(&String::from_utf8_lossy(
+                        &target.essid,
+                    )))
+                    .subtitle(&format_mac(&target.mac_ap))
+                    .build();
+                let label = gtk::Label::new(Some("Cracking..."));
+                label.set_ellipsize(gtk::pango::EllipsizeMode::Middle);
+                label.add_css_class("dim-label");
+                row.add_suffix(&label);
+                imp.cracking_targets_list.append(&row);
+                label
+            })
+            .collect()
+    }
+
+    fn reset_cracking_status(&self) {
+        let imp = self.imp();
+        imp.cracking_progress.set_fraction(0.0);
+        imp.cracking_source_progress.set_fraction(0.0);
+        self.show_cracking_status(&Status::default(), false);
+    }
+
+    fn show_outcome(&self, targets: &[PmkidTarget], outcome: io::Result<Outcome>) {
+        let imp = self.imp();
+        imp.crack_job.take();
+        self.reset_cracking_status();
+
+        let outcome = match outcome {
+            Ok(outcome) => outcome,
+            Err(err) => {
+                self.show_error(&format!("Couldn't read the wordlists: {}", err));
+                return;
+            }
+        };
+
+        // One block per target, for however many of them were found
+        let results = targets
+            .iter()
+            .enumerate()
+            .map(|(index, target)| {
+                let passphrase = match outcome.passphrase(index) {
+                    Some(passphrase) => format!(
+                        "<b>{}</b>",
+                        glib::markup_escape_text(&format_passphrase(passphrase))
+                    ),
+                    None => String::from("Not found"),
+                };
+                let mut result = format!(
+                    "PMKID Hash: {}\n\nPassphrase: {}",
+                    hex::encode(target.pmkid),
+                    passphrase
+                );
+                if targets.len() > 1 {
+                    result = format!(
+                        "Network: {}\n{}",
+                        glib::markup_escape_text(&String::from_utf8_lossy(&target.essid)),
+                        result.replace("\n\n", "\n")
+                    );
+                }
+                result
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        match outcome {
+            Outcome::Found {
+                elapsed,
+                skipped,
+                from_potfile,
+                ..
+            } => {
+                let note = if from_potfile {
+                    String::from("Cracked before, found in the potfile")
+                } else {
+                    format!(
+                        "Time Taken: {}{}",
+                        format_duration(elapsed),
+                        skipped_note(&skipped)
+                    )
+                };
+                imp.success_status_page
+                    .set_description(Some(&format!("{}\n\n{}", results, note)));
+                self.page_switch("success_page");
+            }
+            Outcome::Exhausted { found, skipped, .. } if found.iter().any(Option::is_some) => self
+                .show_error(&format!(
+                    "Found {} of {}{}\n\n{}",
+                    found.iter().flatten().count(),
+                    targets.len(),
+                    skipped_note(&skipped),
+                    results
+                )),
+            Outcome::Exhausted { skipped, .. } => {
+                self.show_error(&format!("No match found{}", skipped_note(&skipped)))
+            }
+            // Whoever cancelled has already moved the window on.
+            Outcome::Cancelled { .. } => {}
+        }
+    }
+}
+
+/// Loads the rule file of a checkpoint again, or says why it can't be.
+fn load_rule_file(rules: Option<&FileIdentity>) -> Result<Option<RuleFile>, String> {
+    let rules = match rules {
+        Some(rules) => rules,
+        None => return Ok(None),
+    };
+    match RuleSet::load(&rules.path) {
+        Ok(rule_set) => Ok(Some(RuleFile {
+            path: rules.path.clone(),
+            rules: Arc::new(rule_set),
+        })),
+        Err(err) => Err(format!("{}: {}", rules.path.display(), err)),
+    }
+}
+
+/// Everything the history search looks through for `entry`, in lowercase.
+fn history_text(entry: &HistoryEntry) -> String {
+    let mut text = format!(
+        "{} {} {} {} {}",
+        String::from_utf8_lossy(&entry.target.essid),
+        format_mac(&entry.target.mac_ap),
+        entry.attack,
+        entry.sources.join(" "),
+        entry.rules.join(" ")
+    );
+    if let HistoryOutcome::Found { passphrase } = &entry.outcome {
+        text.push(' ');
+        text.push_str(passphrase);
+    }
+    text.to_lowercase()
+}
+
+/// Writes a MAC address the usual way, e.g. `aa:bb:cc:dd:ee:ff`.
+fn format_mac(mac: &[u8; 6]) -> String {
+    mac.iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Writes `n` with thousands separators, so that big keyspaces can be read.
+fn group_digits(n: u64) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// A line about the candidates the filter skipped, for the result pages.
+fn skipped_note(skipped: &SkipStats) -> String {
+    if skipped.total() == 0 {
+        String::new()
+    } else {
+        format!("\n\n{}", skipped)
     }
 }
@@ -0,0 +1,76 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tests of writing [`Checkpoint`]s out and reading them back, including
+//! those written by older versions.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use ramsh::engine::{Attack, CandidateFilter, Checkpoint, Checkpointer, PmkidTarget, Position};
+
+const HASH_LINE: &str =
+    "2582a8281bf9d4308d6f5731d0e61c61*4604ba734d4e*89acf0e761f4*ed487162465a774bfba60eb603a39f3a";
+
+/// A path of its own for each test, in a directory that doesn't exist yet.
+fn temp_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ramsh-test-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    dir.join("session.json")
+}
+
+fn wordlist_attack() -> Attack {
+    Attack::Wordlist {
+        wordlists: Vec::new(),
+        rules: None,
+    }
+}
+
+/// The filter a run was started with comes back when resuming it.
+#[test]
+fn filter_round_trip() {
+    let path = temp_path("filter");
+    let mut checkpoint = Checkpoint::new(
+        vec![PmkidTarget::from_hash_line(HASH_LINE).unwrap()],
+        wordlist_attack(),
+    );
+    checkpoint.filter = CandidateFilter {
+        dedupe: true,
+        raw_psk: true,
+    };
+    Checkpointer::new(path.clone(), checkpoint.clone(), Duration::ZERO)
+        .save()
+        .unwrap();
+
+    assert_eq!(Checkpoint::load(&path).unwrap(), Some(checkpoint));
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+/// Checkpoints from before the filter was saved resume with the default
+/// one.
+#[test]
+fn without_filter() {
+    let path = temp_path("no-filter");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(
+        &path,
+        format!(
+            r#"{{"target":"{}","attack":{{"mode":"wordlist","wordlists":[]}},"offset":12}}"#,
+            HASH_LINE
+        ),
+    )
+    .unwrap();
+
+    let checkpoint = Checkpoint::load(&path).unwrap().unwrap();
+    assert_eq!(checkpoint.filter, CandidateFilter::default());
+    assert_eq!(checkpoint.attack, wordlist_attack());
+    assert_eq!(
+        checkpoint.position,
+        Position {
+            source: 0,
+            offset: 12
+        }
+    );
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
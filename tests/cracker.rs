@@ -0,0 +1,57 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tests of running a [`Cracker`] over a wordlist, against the PMKID example
+//! of hashcat's wiki, whose passphrase is `hashcat!`.
+
+use ramsh::engine::{Cracker, NoTargets, Outcome, PmkidTarget, Wordlist};
+
+const HASH_LINE: &str =
+    "2582a8281bf9d4308d6f5731d0e61c61*4604ba734d4e*89acf0e761f4*ed487162465a774bfba60eb603a39f3a";
+
+fn example() -> PmkidTarget {
+    PmkidTarget::from_hash_line(HASH_LINE).unwrap()
+}
+
+fn run(cracker: &Cracker, words: &str) -> Outcome {
+    cracker.run(Wordlist::from_text(words.to_string())).unwrap()
+}
+
+#[test]
+fn no_targets() {
+    assert_eq!(Cracker::with_targets(Vec::new()).err(), Some(NoTargets));
+}
+
+#[test]
+fn finds_passphrase() {
+    let cracker = Cracker::new(example());
+    match run(&cracker, "password\nhashcat\nhashcat!\nletmein1\n") {
+        Outcome::Found { passphrases, .. } => assert_eq!(passphrases, [b"hashcat!"]),
+        outcome => panic!("{:?}", outcome),
+    }
+
+    match run(&cracker, "password\nletmein1\n") {
+        Outcome::Exhausted { found, tried, .. } => {
+            assert_eq!(found, [None]);
+            assert_eq!(tried, 2);
+        }
+        outcome => panic!("{:?}", outcome),
+    }
+}
+
+/// Targets that aren't found are reported as such, alongside those that
+/// were.
+#[test]
+fn several_targets() {
+    let other = PmkidTarget {
+        pmkid: [0; 16],
+        ..example()
+    };
+    let cracker = Cracker::with_targets(vec![other, example()]).unwrap();
+    match run(&cracker, "hashcat!\n") {
+        Outcome::Exhausted { found, .. } => {
+            assert_eq!(found, [None, Some(b"hashcat!".to_vec())]);
+        }
+        outcome => panic!("{:?}", outcome),
+    }
+}
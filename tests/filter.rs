@@ -0,0 +1,151 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tests of which candidates a [`CandidateFilter`] lets a [`Cracker`] try,
+//! against the PMKID example of hashcat's wiki, whose passphrase is
+//! `hashcat!`.
+
+use ramsh::engine::{
+    format_passphrase, CandidateFilter, Cracker, DefaultPbkdf2, Outcome, Pbkdf2Sha1, PmkidTarget,
+    SkipStats, Wordlist, WordlistQueue,
+};
+
+const HASH_LINE: &str =
+    "2582a8281bf9d4308d6f5731d0e61c61*4604ba734d4e*89acf0e761f4*ed487162465a774bfba60eb603a39f3a";
+
+fn example() -> PmkidTarget {
+    PmkidTarget::from_hash_line(HASH_LINE).unwrap()
+}
+
+/// A wordlist of `candidates`, in `$HEX[...]` notation where they need it.
+fn wordlist(candidates: &[&[u8]]) -> Wordlist {
+    let lines: String = candidates
+        .iter()
+        .map(|candidate| format_passphrase(candidate) + "\n")
+        .collect();
+    Wordlist::from_text(lines)
+}
+
+/// Runs `filter` over each of `wordlists` in turn, returning how many
+/// candidates were tried and why the others were skipped.
+fn run(filter: CandidateFilter, wordlists: &[&[&[u8]]]) -> (u64, SkipStats) {
+    let mut cracker = Cracker::new(example());
+    cracker.set_filter(filter);
+    let mut queue = WordlistQueue::new();
+    for candidates in wordlists {
+        queue.push(wordlist(candidates));
+    }
+    match cracker.run(queue).unwrap() {
+        Outcome::Exhausted { tried, skipped, .. } => (tried, skipped),
+        outcome => panic!("{:?}", outcome),
+    }
+}
+
+#[test]
+fn length_bounds() {
+    let (tried, skipped) = run(
+        CandidateFilter::default(),
+        &[&[b"", b"1234567", b"12345678", &[b'a'; 63], &[b'a'; 64]]],
+    );
+    assert_eq!(tried, 2);
+    assert_eq!(skipped.too_short, 2);
+    assert_eq!(skipped.too_long, 1);
+    assert_eq!(skipped.total(), 3);
+}
+
+#[test]
+fn control_characters() {
+    let (tried, skipped) = run(
+        CandidateFilter::default(),
+        &[&[
+            b"pass\tword",
+            b"password\r",
+            b"pass\x7fword",
+            b"p\xc3\xa4ssword",
+            b"~ !\"#$%&'",
+        ]],
+    );
+    assert_eq!(tried, 2);
+    assert_eq!(skipped.unprintable, 3);
+}
+
+#[test]
+fn dedupe() {
+    let candidates: &[&[u8]] = &[b"password", b"12345678", b"password", b"short"];
+    let (tried, skipped) = run(CandidateFilter::default(), &[candidates]);
+    assert_eq!(tried, 3);
+    assert_eq!(skipped.duplicate, 0);
+
+    let filter = CandidateFilter {
+        dedupe: true,
+        ..Default::default()
+    };
+    let (tried, skipped) = run(filter, &[candidates]);
+    assert_eq!(tried, 2);
+    assert_eq!(skipped.duplicate, 1);
+    assert_eq!(skipped.too_short, 1);
+
+    // Across batches too, which never span two wordlists
+    let (tried, skipped) = run(filter, &[&[b"password"], &[b"password", b"iloveyou"]]);
+    assert_eq!(tried, 2);
+    assert_eq!(skipped.duplicate, 1);
+}
+
+#[test]
+fn raw_psk() {
+    let psk = b"f42c6fc52df0ebef9ebb4b90b38a5f902e83fe1b135a70e23aed762e9710a12e";
+    let not_hex = b"g42c6fc52df0ebef9ebb4b90b38a5f902e83fe1b135a70e23aed762e9710a12e";
+    let (tried, skipped) = run(CandidateFilter::default(), &[&[psk, not_hex]]);
+    assert_eq!(tried, 0);
+    assert_eq!(skipped.too_long, 2);
+
+    let filter = CandidateFilter {
+        raw_psk: true,
+        ..Default::default()
+    };
+    assert!(filter.is_raw_psk(psk));
+    assert!(!filter.is_raw_psk(not_hex));
+    assert!(!filter.is_raw_psk(&psk[1..]));
+    let (tried, skipped) = run(filter, &[&[psk, not_hex]]);
+    assert_eq!(tried, 1);
+    assert_eq!(skipped.too_long, 1);
+
+    // The PMK of the example, written out, cracks it as the passphrase does
+    let mut pmk = [0; 32];
+    DefaultPbkdf2::pbkdf2(b"hashcat!", &example().essid, 4096, &mut pmk);
+    let mut cracker = Cracker::new(example());
+    cracker.set_filter(filter);
+    match cracker
+        .run(wordlist(&[hex::encode(pmk).as_bytes()]))
+        .unwrap()
+    {
+        Outcome::Found { passphrases, .. } => {
+            assert_eq!(passphrases, [hex::encode(pmk).into_bytes()]);
+        }
+        outcome => panic!("{:?}", outcome),
+    }
+}
+
+#[test]
+fn skip_stats() {
+    let mut stats = SkipStats {
+        too_short: 1,
+        too_long: 2,
+        unprintable: 3,
+        duplicate: 4,
+    };
+    stats += stats;
+    assert_eq!(stats.total(), 20);
+    assert_eq!(
+        stats.to_string(),
+        "20 candidates skipped (2 too short, 4 too long, 6 unprintable, 8 duplicate)"
+    );
+    let one = SkipStats {
+        too_short: 1,
+        ..Default::default()
+    };
+    assert_eq!(
+        one.to_string(),
+        "1 candidate skipped (1 too short, 0 too long, 0 unprintable, 0 duplicate)"
+    );
+}
@@ -0,0 +1,233 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tests of reading the hash lines hcxpcapngtool writes for hashcat's modes
+//! 22000, 22001 and 16800.
+
+use ramsh::engine::{format_hash_lines, read_hash_lines, HashLineError, PmkidTarget, TargetError};
+
+const PMKID: &str = "2582a8281bf9d4308d6f5731d0e61c61";
+const MAC_AP: &str = "4604ba734d4e";
+const MAC_STA: &str = "89acf0e761f4";
+const ESSID: &str = "ed487162465a774bfba60eb603a39f3a";
+
+fn example() -> PmkidTarget {
+    PmkidTarget {
+        essid: hex::decode(ESSID).unwrap(),
+        mac_ap: [0x46, 0x04, 0xba, 0x73, 0x4d, 0x4e],
+        mac_sta: [0x89, 0xac, 0xf0, 0xe7, 0x61, 0xf4],
+        pmkid: [
+            0x25, 0x82, 0xa8, 0x28, 0x1b, 0xf9, 0xd4, 0x30, 0x8d, 0x6f, 0x57, 0x31, 0xd0, 0xe6,
+            0x1c, 0x61,
+        ],
+    }
+}
+
+fn parse(line: &str) -> Result<PmkidTarget, TargetError> {
+    PmkidTarget::from_hash_line(line)
+}
+
+fn invalid(line: &str) -> Result<PmkidTarget, TargetError> {
+    Err(TargetError::InvalidHashLine(line.to_string()))
+}
+
+#[test]
+fn valid_lines() {
+    let lines = [
+        format!("WPA*01*{}*{}*{}*{}***", PMKID, MAC_AP, MAC_STA, ESSID),
+        // The message pair can be left out, and so can the fields before it
+        format!("WPA*01*{}*{}*{}*{}**", PMKID, MAC_AP, MAC_STA, ESSID),
+        format!("WPA*01*{}*{}*{}*{}", PMKID, MAC_AP, MAC_STA, ESSID),
+        format!("WPA*01*{}*{}*{}*{}***01", PMKID, MAC_AP, MAC_STA, ESSID),
+        format!("{}*{}*{}*{}", PMKID, MAC_AP, MAC_STA, ESSID),
+        format!("  {}*{}*{}*{}\r", PMKID, MAC_AP, MAC_STA, ESSID),
+        format!(
+            "{}*{}*{}*{}",
+            PMKID.to_uppercase(),
+            MAC_AP,
+            MAC_STA,
+            ESSID.to_uppercase()
+        ),
+    ];
+    for line in &lines {
+        assert_eq!(parse(line), Ok(example()), "{:?}", line);
+    }
+    assert_eq!(
+        example().to_hash_line(),
+        format!("{}*{}*{}*{}", PMKID, MAC_AP, MAC_STA, ESSID)
+    );
+}
+
+/// A PMKID record comes without the nonce and EAPOL frame of a handshake.
+#[test]
+fn pmkid_records_only() {
+    let line = format!("WPA*01*{}*{}*{}*{}*aabb**", PMKID, MAC_AP, MAC_STA, ESSID);
+    assert_eq!(parse(&line), invalid(&line));
+    let line = format!(
+        "WPA*01*{}*{}*{}*{}**0103005f**",
+        PMKID, MAC_AP, MAC_STA, ESSID
+    );
+    assert_eq!(parse(&line), invalid(&line));
+
+    let line = format!(
+        "WPA*02*{}*{}*{}*{}*aabb*0103005f*02",
+        PMKID, MAC_AP, MAC_STA, ESSID
+    );
+    assert_eq!(
+        parse(&line),
+        Err(TargetError::UnsupportedRecord(String::from("02")))
+    );
+    let line = format!("WPA*03*{}*{}*{}*{}***", PMKID, MAC_AP, MAC_STA, ESSID);
+    assert_eq!(parse(&line), invalid(&line));
+}
+
+#[test]
+fn invalid_fields() {
+    assert_eq!(
+        parse(&format!("{}*{}*{}*{}", "25zz", MAC_AP, MAC_STA, ESSID)),
+        Err(TargetError::InvalidPmkid(String::from("25zz")))
+    );
+    assert_eq!(
+        parse(&format!("{}00*{}*{}*{}", PMKID, MAC_AP, MAC_STA, ESSID)),
+        Err(TargetError::InvalidPmkid(format!("{}00", PMKID)))
+    );
+    assert_eq!(
+        parse(&format!("{}*{}*{}*{}", PMKID, "4604ba734d", MAC_STA, ESSID)),
+        Err(TargetError::InvalidMac(String::from("4604ba734d")))
+    );
+    assert_eq!(
+        parse(&format!(
+            "{}*{}*{}*{}",
+            PMKID, MAC_AP, "89acf0e761fg", ESSID
+        )),
+        Err(TargetError::InvalidMac(String::from("89acf0e761fg")))
+    );
+    assert_eq!(
+        parse(&format!("{}*{}*{}*{}", PMKID, MAC_AP, MAC_STA, "ed4")),
+        Err(TargetError::InvalidEssid(String::from("ed4")))
+    );
+    assert_eq!(
+        parse(&format!("WPA*01*{}*{}*{}****", PMKID, MAC_AP, MAC_STA)),
+        Err(TargetError::EmptyEssid)
+    );
+}
+
+#[test]
+fn wrong_field_counts() {
+    let lines = [
+        String::new(),
+        format!("{}*{}*{}", PMKID, MAC_AP, MAC_STA),
+        format!("{}*{}*{}*{}*", PMKID, MAC_AP, MAC_STA, ESSID),
+        format!("WPA*01*{}*{}*{}", PMKID, MAC_AP, MAC_STA),
+        format!("WPA*01*{}*{}*{}*{}****", PMKID, MAC_AP, MAC_STA, ESSID),
+        format!("{}:{}:{}:{}", PMKID, MAC_AP, MAC_STA, ESSID),
+    ];
+    for line in &lines {
+        assert_eq!(parse(line), invalid(line.trim()), "{:?}", line);
+    }
+}
+
+/// Blank lines are skipped, duplicates are dropped in order, and errors
+/// carry the number of their line.
+#[test]
+fn read_file() {
+    let other = format!("{}*{}*{}*686f6d65", "ab".repeat(16), MAC_AP, MAC_STA);
+    let text = format!(
+        "WPA*01*{pmkid}*{ap}*{sta}*{essid}***\r\n\
+         \r\n\
+         {other}\r\n\
+         not a hash line\n\
+         {pmkid}*{ap}*{sta}*{essid}\n\
+         WPA*02*{pmkid}*{ap}*{sta}*{essid}*aabb*0103005f*02\n\
+         \x20\x20\n\
+         {other}\n",
+        pmkid = PMKID,
+        ap = MAC_AP,
+        sta = MAC_STA,
+        essid = ESSID,
+        other = other,
+    );
+
+    let (targets, errors) = read_hash_lines(&text);
+    assert_eq!(targets, [example(), parse(&other).unwrap()]);
+    assert_eq!(
+        errors,
+        [
+            HashLineError {
+                line: 4,
+                error: TargetError::InvalidHashLine(String::from("not a hash line")),
+            },
+            HashLineError {
+                line: 6,
+                error: TargetError::UnsupportedRecord(String::from("02")),
+            },
+        ]
+    );
+    assert_eq!(
+        errors[1].to_string(),
+        "line 6: WPA*02 records can't be cracked, only PMKIDs (WPA*01)"
+    );
+
+    assert_eq!(read_hash_lines(""), (Vec::new(), Vec::new()));
+}
+
+/// Many targets, each more than once, still come out once each and in the
+/// order they were first seen.
+#[test]
+fn read_many_duplicates() {
+    let lines: Vec<String> = (0..2000u32)
+        .map(|i| {
+            format!(
+                "{:032x}*{}*{}*{}",
+                i % 1000,
+                MAC_AP,
+                MAC_STA,
+                hex::encode(format!("net{}", i % 1000))
+            )
+        })
+        .collect();
+    let (targets, errors) = read_hash_lines(&lines.join("\n"));
+    assert!(errors.is_empty());
+    assert_eq!(targets.len(), 1000);
+    for (i, target) in targets.iter().enumerate() {
+        assert_eq!(target.to_hash_line(), lines[i]);
+    }
+}
+
+/// Targets written out as 16800 lines read back the same, whatever bytes
+/// their ESSID is made of.
+#[test]
+fn export_round_trip() {
+    let mut targets = vec![example()];
+    for (i, essid) in [
+        &b"home*net"[..],
+        "caf\u{e9} \u{1f4f6}".as_bytes(),
+        b"\x00\xff\r\n:*",
+        b"x",
+        &[b'*'; 32],
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        targets.push(PmkidTarget {
+            essid: essid.to_vec(),
+            mac_sta: [i as u8; 6],
+            ..example()
+        });
+    }
+
+    let text = format_hash_lines(&targets);
+    assert_eq!(text.lines().count(), targets.len());
+    for line in text.lines() {
+        assert_eq!(line.split('*').count(), 4, "{:?}", line);
+    }
+    assert_eq!(read_hash_lines(&text), (targets.clone(), Vec::new()));
+
+    // Each line also works on its own, e.g. pasted into the PMKID entry
+    for (line, target) in text.lines().zip(&targets) {
+        assert_eq!(
+            PmkidTarget::from_strings("", "", "", line).as_ref(),
+            Ok(target)
+        );
+    }
+}
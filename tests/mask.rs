@@ -0,0 +1,114 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tests of how a [`MaskSpec`] increments, on its own and in hybrid attacks
+//! like hashcat's `-a 6` and `-a 7`.
+
+use ramsh::engine::{
+    CandidateSource, Hybrid, HybridSide, Mask, MaskError, MaskSpec, Wordlist, MAX_PASSPHRASE_LEN,
+};
+
+fn spec(mask: &str, increment: Option<usize>) -> MaskSpec {
+    MaskSpec {
+        mask: mask.to_string(),
+        charsets: Vec::new(),
+        increment: increment.map(|min| min..=MAX_PASSPHRASE_LEN),
+    }
+}
+
+fn lengths(masks: &[Mask]) -> Vec<usize> {
+    masks.iter().map(Mask::len).collect()
+}
+
+fn hybrid(words: &str, masks: Vec<Mask>, side: HybridSide) -> Vec<String> {
+    let count = words.lines().count() as u64;
+    let mut candidates = Vec::new();
+    for mask in masks {
+        let mut source = Hybrid::new(Wordlist::from_text(words.to_string()), mask, side, count);
+        while let Some(batch) = source.next_batch().unwrap() {
+            candidates.extend(
+                batch
+                    .candidates
+                    .into_iter()
+                    .map(|candidate| String::from_utf8(candidate).unwrap()),
+            );
+        }
+        assert_eq!(source.progress(), source.total());
+    }
+    candidates
+}
+
+/// A mask on its own skips lengths that are too short for a passphrase, even
+/// when incrementing from 1.
+#[test]
+fn increment_on_its_own() {
+    let masks = spec("?d?d?d?d?d?d?d?d?d?d", Some(1))
+        .passphrase_masks()
+        .unwrap();
+    assert_eq!(lengths(&masks), [8, 9, 10]);
+    assert_eq!(
+        spec("?d?d?d?d?d?d?d?d?d?d", Some(1)).too_short_lengths(),
+        Ok(vec![1, 2, 3, 4, 5, 6, 7])
+    );
+    let masks = spec("?d?d?d?d?d?d?d?d?d?d", Some(9))
+        .passphrase_masks()
+        .unwrap();
+    assert_eq!(lengths(&masks), [9, 10]);
+    assert_eq!(
+        spec("?d?d?d?d?d?d?d?d?d?d", Some(9)).too_short_lengths(),
+        Ok(Vec::new())
+    );
+    assert_eq!(
+        spec("?d?d?d?d?d?d?d?d?d?d", Some(1)).passphrase_keyspace(),
+        Ok(100_000_000 + 1_000_000_000 + 10_000_000_000)
+    );
+
+    assert_eq!(
+        spec("?d?d?d?d", Some(1)).passphrase_masks(),
+        Err(MaskError::TooShort)
+    );
+    // Without incrementing, the mask is tried as it is
+    assert_eq!(
+        lengths(&spec("?d?d?d?d", None).passphrase_masks().unwrap()),
+        [4]
+    );
+    assert_eq!(spec("?d?d?d?d", None).too_short_lengths(), Ok(Vec::new()));
+}
+
+/// In a hybrid attack, the lengths are those of the mask, not of the whole
+/// candidate.
+#[test]
+fn increment_hybrid() {
+    let spec = spec("?d?d?d?d", Some(1));
+    let masks = spec.masks().unwrap();
+    assert_eq!(lengths(&masks), [1, 2, 3, 4]);
+    assert_eq!(spec.keyspace(), Ok(10 + 100 + 1000 + 10_000));
+
+    let masks = spec.masks().unwrap();
+    let appended = hybrid("sunshine\nhello\n", masks, HybridSide::Append);
+    assert_eq!(appended.len(), 2 * 11_110);
+    assert_eq!(appended[0], "sunshine0");
+    assert_eq!(appended[10], "hello0");
+    assert_eq!(appended[20], "sunshine00");
+    assert_eq!(appended.last().unwrap(), "hello9999");
+
+    let masks = spec.masks().unwrap();
+    let prepended = hybrid("sunshine\nhello\n", masks, HybridSide::Prepend);
+    assert_eq!(prepended.len(), 2 * 11_110);
+    assert_eq!(prepended[0], "0sunshine");
+    assert_eq!(prepended[10], "0hello");
+    assert_eq!(prepended[29], "09sunshine");
+    assert!(prepended.contains(&String::from("2023sunshine")));
+}
+
+#[test]
+fn increment_bounds() {
+    let mut spec = spec("?l?l?l?l?l", Some(2));
+    spec.increment = Some(2..=3);
+    assert_eq!(lengths(&spec.masks().unwrap()), [2, 3]);
+    spec.increment = Some(6..=8);
+    assert_eq!(spec.masks(), Err(MaskError::TooShort));
+    // Lengths past the end of the mask are left out
+    spec.increment = Some(0..=63);
+    assert_eq!(lengths(&spec.masks().unwrap()), [1, 2, 3, 4, 5]);
+}
@@ -0,0 +1,147 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Known-answer tests for the PBKDF2 backends, against each other and
+//! against the scalar path, and for the libraries behind the scalar path.
+
+#[cfg(feature = "backend-fastpbkdf2")]
+use ramsh::engine::FastPbkdf2;
+#[cfg(feature = "backend-rustcrypto")]
+use ramsh::engine::RustCrypto;
+use ramsh::engine::{Pbkdf2Backend, Pbkdf2Sha1, MAX_LANES};
+
+fn supported() -> impl Iterator<Item = Pbkdf2Backend> {
+    Pbkdf2Backend::ALL
+        .into_iter()
+        .filter(|backend| backend.is_supported())
+}
+
+fn derive(backend: Pbkdf2Backend, essid: &[u8], passphrases: &[&[u8]]) -> Vec<[u8; 32]> {
+    let mut pmks = Vec::new();
+    for group in passphrases.chunks(backend.lanes()) {
+        let mut out = [[0; 32]; MAX_LANES];
+        backend.derive_pmks(essid, group, &mut out);
+        pmks.extend_from_slice(&out[..group.len()]);
+    }
+    pmks
+}
+
+/// The test vectors from IEEE 802.11i, annex H.4.
+#[test]
+fn ieee_vectors() {
+    let vectors: [(&[u8], &[u8], &str); 2] = [
+        (
+            b"password",
+            b"IEEE",
+            "f42c6fc52df0ebef9ebb4b90b38a5f902e83fe1b135a70e23aed762e9710a12e",
+        ),
+        (
+            b"ThisIsAPassword",
+            b"ThisIsASSID",
+            "0dc0d6eb90555ed6419756b9a15ec3e3209b63df707dd508d14581f8982721af",
+        ),
+    ];
+
+    for backend in supported() {
+        for (passphrase, essid, pmk) in vectors {
+            let pmks = derive(backend, essid, &[passphrase]);
+            assert_eq!(hex::encode(pmks[0]), pmk, "{}", backend);
+        }
+    }
+}
+
+/// Every backend against the scalar one, with passphrases and ESSIDs of
+/// all the lengths that change how SHA-1 pads them, and groups that don't
+/// fill every lane.
+#[test]
+fn backends_agree() {
+    let passphrases: Vec<Vec<u8>> = (0..37)
+        .map(|i| {
+            let len = [8, 13, 55, 56, 63, 64, 65][i % 7];
+            (0..len).map(|j| b'!' + ((i * 7 + j) % 90) as u8).collect()
+        })
+        .collect();
+    let passphrases: Vec<&[u8]> = passphrases.iter().map(Vec::as_slice).collect();
+
+    for essid in [&b""[..], b"a", b"linksys", &[b'x'; 32], &[0xff; 51]] {
+        let expected = derive(Pbkdf2Backend::Scalar, essid, &passphrases);
+        for backend in supported() {
+            for count in [1, 3, passphrases.len()] {
+                let pmks = derive(backend, essid, &passphrases[..count]);
+                assert_eq!(pmks, expected[..count], "{} with {:?}", backend, essid);
+            }
+        }
+    }
+}
+
+#[test]
+fn detect_is_supported() {
+    assert!(Pbkdf2Backend::detect().is_supported());
+    for backend in Pbkdf2Backend::ALL {
+        assert_eq!(Pbkdf2Backend::from_name(backend.name()), Some(backend));
+    }
+}
+
+/// The PBKDF2-HMAC-SHA1 vectors of RFC 6070 and the HMAC-SHA1 ones of
+/// RFC 2202, short and long keys alike.
+fn check_library<L: Pbkdf2Sha1>() {
+    let mut out = [0; 20];
+    L::pbkdf2(b"password", b"salt", 4096, &mut out);
+    assert_eq!(hex::encode(out), "4b007901b765489abead49d926f721d065a429c1");
+
+    let mut out = [0; 25];
+    L::pbkdf2(
+        b"passwordPASSWORDpassword",
+        b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+        4096,
+        &mut out,
+    );
+    assert_eq!(
+        hex::encode(out),
+        "3d2eec4fe41c849b80c8d83662c0e44a8b291a964cf2f07038"
+    );
+
+    assert_eq!(
+        hex::encode(L::hmac(&[0x0b; 20], b"Hi There")),
+        "b617318655057264e28bc0b6fb378c8ef146be00"
+    );
+    assert_eq!(
+        hex::encode(L::hmac(
+            &[0xaa; 80],
+            b"Test Using Larger Than Block-Size Key - Hash Key First"
+        )),
+        "aa4ae5e15272d00e95705637ce8a3b55ed402112"
+    );
+}
+
+#[cfg(feature = "backend-fastpbkdf2")]
+#[test]
+fn fastpbkdf2_vectors() {
+    check_library::<FastPbkdf2>();
+}
+
+#[cfg(feature = "backend-rustcrypto")]
+#[test]
+fn rustcrypto_vectors() {
+    check_library::<RustCrypto>();
+}
+
+#[cfg(all(feature = "backend-fastpbkdf2", feature = "backend-rustcrypto"))]
+#[test]
+fn libraries_agree() {
+    for len in [0, 1, 8, 20, 63, 64, 65, 100] {
+        let input: Vec<u8> = (0..len).map(|i| (i * 37 % 256) as u8).collect();
+        for rounds in [1, 2, 4096] {
+            let (mut fast, mut rust) = ([0; 32], [0; 32]);
+            FastPbkdf2::pbkdf2(&input, b"ramsh", rounds, &mut fast);
+            RustCrypto::pbkdf2(&input, b"ramsh", rounds, &mut rust);
+            assert_eq!(fast, rust, "{} bytes, {} rounds", len, rounds);
+        }
+        assert_eq!(
+            FastPbkdf2::hmac(&input, b"PMK Name"),
+            RustCrypto::hmac(&input, b"PMK Name"),
+            "{} bytes",
+            len
+        );
+    }
+}
@@ -0,0 +1,131 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tests of reading potfiles in the formats hashcat writes, and of adding
+//! passphrases to them.
+
+use std::fs;
+use std::path::PathBuf;
+
+use ramsh::engine::{PmkidTarget, Potfile};
+
+/// The PMKID example of hashcat's wiki, whose passphrase is `hashcat!`.
+const HASH_LINE: &str =
+    "2582a8281bf9d4308d6f5731d0e61c61*4604ba734d4e*89acf0e761f4*ed487162465a774bfba60eb603a39f3a";
+
+fn target(hash_line: &str) -> PmkidTarget {
+    PmkidTarget::from_hash_line(hash_line).unwrap()
+}
+
+/// A path of its own for each test, in a directory that doesn't exist yet.
+fn temp_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ramsh-test-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    dir.join("ramsh.potfile")
+}
+
+fn load(name: &str, contents: &str) -> Potfile {
+    let path = temp_path(name);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, contents).unwrap();
+    Potfile::load(path).unwrap()
+}
+
+#[test]
+fn legacy_lines() {
+    let potfile = load(
+        "legacy",
+        &format!(
+            "{}:hashcat!\r\n\
+             aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa*4604ba734d4e*89acf0e761f4*686f6d65:$HEX[00ff3a]\n\
+             bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb*4604ba734d4e*89acf0e761f4*686f6d65:a:b:c\n\
+             \n\
+             5f4dcc3b5aa765d61d8327deb882cf99:password\n",
+            HASH_LINE
+        ),
+    );
+    assert_eq!(potfile.get(&target(HASH_LINE)).unwrap(), b"hashcat!");
+    assert_eq!(
+        potfile
+            .get(&target(
+                "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa*4604ba734d4e*89acf0e761f4*686f6d65"
+            ))
+            .unwrap(),
+        b"\x00\xff:"
+    );
+    assert_eq!(
+        potfile
+            .get(&target(
+                "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb*4604ba734d4e*89acf0e761f4*686f6d65"
+            ))
+            .unwrap(),
+        b"a:b:c"
+    );
+    // The MD5 line is for another hash type, the blank one doesn't count
+    assert_eq!(potfile.skipped(), 1);
+}
+
+/// Newer hashcat versions write the fields of mode 16800 and 22000 apart,
+/// with the ESSID as text unless it isn't printable.
+#[test]
+fn hashcat_lines() {
+    let potfile = load(
+        "hashcat",
+        "2582a8281bf9d4308d6f5731d0e61c61:4604ba734d4e:89acf0e761f4:\
+         $HEX[ed487162465a774bfba60eb603a39f3a]:hashcat!\n\
+         aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa:4604ba734d4e:89acf0e761f4:home:$HEX[68656c6c6f]\n\
+         bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb:4604ba734d4e:89acf0e761f4:home:pass:word\r\n\
+         cccccccccccccccccccccccccccccccc:4604ba734d4e:89acf0e761f4:home\n\
+         not-a-pmkid:4604ba734d4e:89acf0e761f4:home:password\n",
+    );
+    assert_eq!(potfile.get(&target(HASH_LINE)).unwrap(), b"hashcat!");
+    assert_eq!(
+        potfile
+            .get(&target(
+                "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa*4604ba734d4e*89acf0e761f4*686f6d65"
+            ))
+            .unwrap(),
+        b"hello"
+    );
+    assert_eq!(
+        potfile
+            .get(&target(
+                "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb*4604ba734d4e*89acf0e761f4*686f6d65"
+            ))
+            .unwrap(),
+        b"pass:word"
+    );
+    assert_eq!(potfile.skipped(), 2);
+}
+
+#[test]
+fn insert_and_reload() {
+    let path = temp_path("insert");
+    let potfile = Potfile::load(path.clone()).unwrap();
+    assert_eq!(potfile.get(&target(HASH_LINE)), None);
+    assert_eq!(potfile.skipped(), 0);
+
+    let other = target("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa*4604ba734d4e*89acf0e761f4*686f6d65");
+    // An ESSID with a colon in it has to be written as $HEX[...]
+    let colon = target("bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb*4604ba734d4e*89acf0e761f4*613a62");
+    potfile.insert(&target(HASH_LINE), b"hashcat!").unwrap();
+    potfile.insert(&other, b"tab\there").unwrap();
+    potfile.insert(&colon, b"pass:word").unwrap();
+    // Already there, so not written again
+    potfile.insert(&target(HASH_LINE), b"hashcat!").unwrap();
+
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "2582a8281bf9d4308d6f5731d0e61c61:4604ba734d4e:89acf0e761f4:\
+         $HEX[ed487162465a774bfba60eb603a39f3a]:hashcat!\n\
+         aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa:4604ba734d4e:89acf0e761f4:home:$HEX[7461620968657265]\n\
+         bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb:4604ba734d4e:89acf0e761f4:$HEX[613a62]:pass:word\n"
+    );
+
+    let reloaded = Potfile::load(path.clone()).unwrap();
+    assert_eq!(reloaded.get(&target(HASH_LINE)).unwrap(), b"hashcat!");
+    assert_eq!(reloaded.get(&other).unwrap(), b"tab\there");
+    assert_eq!(reloaded.get(&colon).unwrap(), b"pass:word");
+    assert_eq!(reloaded.skipped(), 0);
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
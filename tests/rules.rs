@@ -0,0 +1,288 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Known-answer tests for the rule engine, mostly the `p@ssW0rd` examples
+//! of hashcat's rule documentation, and tests of how [`Mangled`] hands out
+//! and resumes its candidates.
+
+use std::sync::Arc;
+
+use ramsh::engine::{CandidateSource, Mangled, Position, Rule, RuleError, RuleSet, Wordlist};
+
+fn apply(rule: &str, word: &[u8]) -> Option<Vec<u8>> {
+    Rule::parse(rule)
+        .unwrap_or_else(|err| panic!("{:?}: {}", rule, err))
+        .apply(word)
+}
+
+fn check(word: &[u8], cases: &[(&str, &[u8])]) {
+    for &(rule, expected) in cases {
+        assert_eq!(
+            apply(rule, word).as_deref(),
+            Some(expected),
+            "{:?} on {:?}",
+            rule,
+            String::from_utf8_lossy(word)
+        );
+    }
+}
+
+#[test]
+fn case_rules() {
+    check(
+        b"p@ssW0rd",
+        &[
+            (":", b"p@ssW0rd"),
+            ("l", b"p@ssw0rd"),
+            ("u", b"P@SSW0RD"),
+            ("c", b"P@ssw0rd"),
+            ("C", b"p@SSW0RD"),
+            ("t", b"P@SSw0RD"),
+            ("T3", b"p@sSW0rd"),
+            ("T0T1", b"P@ssW0rd"),
+        ],
+    );
+    check(
+        b"pass-WORD and more",
+        &[("E", b"Pass-word And More"), ("e-", b"Pass-Word and more")],
+    );
+}
+
+#[test]
+fn positional_rules() {
+    check(
+        b"p@ssW0rd",
+        &[
+            ("r", b"dr0Wss@p"),
+            ("d", b"p@ssW0rdp@ssW0rd"),
+            ("p2", b"p@ssW0rdp@ssW0rdp@ssW0rd"),
+            ("f", b"p@ssW0rddr0Wss@p"),
+            ("{", b"@ssW0rdp"),
+            ("}", b"dp@ssW0r"),
+            ("$1$2", b"p@ssW0rd12"),
+            ("^2^1", b"12p@ssW0rd"),
+            ("[", b"@ssW0rd"),
+            ("]", b"p@ssW0r"),
+            ("D3", b"p@sW0rd"),
+            ("x04", b"p@ss"),
+            ("O12", b"psW0rd"),
+            ("i4!", b"p@ss!W0rd"),
+            ("i8!", b"p@ssW0rd!"),
+            ("o3$", b"p@s$W0rd"),
+            ("'6", b"p@ssW0"),
+            ("ss$", b"p@$$W0rd"),
+            ("@s", b"p@W0rd"),
+            ("z2", b"ppp@ssW0rd"),
+            ("Z2", b"p@ssW0rddd"),
+            ("q", b"pp@@ssssWW00rrdd"),
+            ("k", b"@pssW0rd"),
+            ("K", b"p@ssW0dr"),
+            ("*34", b"p@sWs0rd"),
+            ("*43", b"p@sWs0rd"),
+            ("L2", b"p@\xe6sW0rd"),
+            ("R2", b"p@9sW0rd"),
+            ("+2", b"p@tsW0rd"),
+            ("-1", b"p?ssW0rd"),
+            (".1", b"psssW0rd"),
+            (",1", b"ppssW0rd"),
+            ("y2", b"p@p@ssW0rd"),
+            ("Y2", b"p@ssW0rdrd"),
+            // Spaces between functions are ignored
+            ("c $1", b"P@ssw0rd1"),
+        ],
+    );
+}
+
+/// Positions past the end of the word leave it as it is, as hashcat's CPU
+/// rule functions do.
+#[test]
+fn positions_out_of_range() {
+    check(
+        b"abc",
+        &[
+            ("T3", b"abc"),
+            ("D3", b"abc"),
+            ("i4!", b"abc"),
+            ("o3!", b"abc"),
+            ("x13", b"abc"),
+            ("O22", b"abc"),
+            ("*03", b"abc"),
+            ("'5", b"abc"),
+            (".2", b"abc"),
+            (",3", b"abc"),
+            ("y4", b"abc"),
+            ("Y4", b"abc"),
+        ],
+    );
+    check(
+        b"",
+        &[("[", b""), ("]", b""), ("{", b""), ("k", b""), ("z2", b"")],
+    );
+}
+
+#[test]
+fn memory_rules() {
+    check(
+        b"p@ssW0rd",
+        &[
+            ("lMX428", b"p@ssw0rdw0"),
+            ("uMl4", b"p@ssw0rdP@SSW0RD"),
+            ("rMr6", b"dr0Wss@pp@ssW0rd"),
+            ("rMrQ", b"p@ssW0rd"),
+        ],
+    );
+    // Palindromes are the same once reversed
+    assert_eq!(apply("rMrQ", b"racecar"), None);
+}
+
+#[test]
+fn rejection_rules() {
+    let word = b"p@ssW0rd";
+    for rule in ["<8", ">8", "_8", "!z", "/s", "(p", ")d", "=1@", "%2s"] {
+        assert_eq!(apply(rule, word).as_deref(), Some(&word[..]), "{:?}", rule);
+    }
+    for rule in ["<7", ">9", "_7", "!s", "/z", "(a", ")a", "=1a", "%3s"] {
+        assert_eq!(apply(rule, word), None, "{:?}", rule);
+    }
+    // Rejections apply to the word as mangled so far
+    assert_eq!(apply("$1<8", word), None);
+    assert_eq!(apply("]<7", word).as_deref(), Some(&b"p@ssW0r"[..]));
+    // Words can't grow past 256 bytes
+    assert_eq!(apply("p9p9", word), None);
+}
+
+#[test]
+fn invalid_rules() {
+    let cases = [
+        ("T", RuleError::MissingArgument('T')),
+        ("$", RuleError::MissingArgument('$')),
+        ("sa", RuleError::MissingArgument('s')),
+        ("i4", RuleError::MissingArgument('i')),
+        ("Tz", RuleError::InvalidPosition('z')),
+        ("x0!", RuleError::InvalidPosition('!')),
+        ("c?", RuleError::UnknownFunction('?')),
+        ("&", RuleError::UnknownFunction('&')),
+    ];
+    for (rule, err) in cases {
+        assert_eq!(Rule::parse(rule), Err(err), "{:?}", rule);
+    }
+}
+
+#[test]
+fn rule_set_lines() {
+    let set = RuleSet::parse(":\n# a comment\nT\n\nc\nu?\r\n$1\nTz\n");
+    assert_eq!(set.len(), 3);
+    assert_eq!(
+        set.invalid(),
+        [
+            (3, RuleError::MissingArgument('T')),
+            (6, RuleError::UnknownFunction('?')),
+            (8, RuleError::InvalidPosition('z')),
+        ]
+    );
+
+    let set = RuleSet::parse(":\nc\n<4\n$1\n");
+    let candidates: Vec<Vec<u8>> = set.apply(b"pass").collect();
+    assert_eq!(candidates, [&b"pass"[..], b"Pass", b"pass", b"pass1"]);
+    let candidates: Vec<Vec<u8>> = set.apply(b"words").collect();
+    assert_eq!(candidates, [&b"words"[..], b"Words", b"words1"]);
+}
+
+/// Rule files are read as bytes, so arguments in other encodings than UTF-8
+/// are kept as they are.
+#[test]
+fn rule_set_bytes() {
+    let set = RuleSet::parse(b"$\xe9\r\n^\xa3\n@\xe9\n# \xff\n");
+    assert_eq!(set.len(), 3);
+    assert!(set.invalid().is_empty());
+    let candidates: Vec<Vec<u8>> = set.apply(b"caf\xe9").collect();
+    assert_eq!(candidates, [&b"caf\xe9\xe9"[..], b"\xa3caf\xe9", b"caf"]);
+}
+
+fn mangled(words: &str, rules: &str) -> Mangled<Wordlist> {
+    let count = words.lines().count() as u64;
+    Mangled::new(
+        Wordlist::from_text(words.to_string()),
+        Arc::new(RuleSet::parse(rules)),
+        count,
+    )
+}
+
+fn collect(source: &mut impl CandidateSource) -> Vec<(Vec<Vec<u8>>, Position)> {
+    let mut batches = Vec::new();
+    while let Some(batch) = source.next_batch().unwrap() {
+        batches.push((batch.candidates, batch.end));
+    }
+    batches
+}
+
+/// Every rule of a big rule file makes for many batches out of a single
+/// word, rather than one huge batch.
+#[test]
+fn mangled_batches() {
+    let rules: String = (0..10_000).map(|i| format!("${}\n", i % 10)).collect();
+    let mut source = mangled("first\nsecond\n", &rules);
+    assert_eq!(source.total(), 20_000);
+
+    let batches = collect(&mut source);
+    assert!(batches.len() > 2);
+    assert!(batches
+        .iter()
+        .all(|(candidates, _)| !candidates.is_empty() && candidates.len() <= 4096));
+    let candidates: Vec<Vec<u8>> = batches
+        .iter()
+        .flat_map(|(candidates, _)| candidates.clone())
+        .collect();
+    assert_eq!(candidates.len(), 20_000);
+    assert_eq!(candidates[0], b"first0");
+    assert_eq!(candidates[10_001], b"second1");
+    assert_eq!(batches.last().unwrap().1.offset, 20_000);
+    assert_eq!(source.progress(), 20_000);
+}
+
+/// Rejected candidates are left out, but still move the position along.
+#[test]
+fn mangled_rejections() {
+    let mut source = mangled("short\nlonger one\n", ":\n>6\n<4\nu\n");
+    let candidates: Vec<Vec<u8>> = collect(&mut source)
+        .into_iter()
+        .flat_map(|(candidates, _)| candidates)
+        .collect();
+    assert_eq!(
+        candidates,
+        [
+            &b"short"[..],
+            b"SHORT",
+            b"longer one",
+            b"longer one",
+            b"LONGER ONE"
+        ]
+    );
+    assert_eq!(source.progress(), 8);
+}
+
+/// Resuming from the end of any batch, even partway through the rules of a
+/// word, gives back exactly the candidates that were left.
+#[test]
+fn mangled_resume() {
+    let words = "alpha\nbravo\ncharlie\n";
+    let rules: String = (0..3000).map(|i| format!("${}\n", i % 10)).collect();
+    let batches = collect(&mut mangled(words, &rules));
+    let all: Vec<Vec<u8>> = batches
+        .iter()
+        .flat_map(|(candidates, _)| candidates.clone())
+        .collect();
+
+    let mut done = 0;
+    for (candidates, end) in &batches {
+        done += candidates.len();
+        let mut source = mangled(words, &rules);
+        source.skip_to(*end).unwrap();
+        assert_eq!(source.progress(), end.offset);
+        let rest: Vec<Vec<u8>> = collect(&mut source)
+            .into_iter()
+            .flat_map(|(candidates, _)| candidates)
+            .collect();
+        assert_eq!(rest, all[done..]);
+    }
+}
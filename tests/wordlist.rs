@@ -0,0 +1,69 @@
+// Copyright 2022 Mufeed Ali
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tests of how a [`Wordlist`] splits its lines into candidates.
+
+use std::io;
+
+use ramsh::engine::{CancelToken, CandidateSource, Wordlist, MAX_LINE_LEN};
+
+fn candidates(text: &str) -> Vec<Vec<u8>> {
+    let mut wordlist = Wordlist::from_text(text.to_string());
+    let mut candidates = Vec::new();
+    while let Some(batch) = wordlist.next_batch().unwrap() {
+        candidates.extend(batch.candidates);
+    }
+    assert_eq!(wordlist.progress(), text.len() as u64);
+    candidates
+}
+
+fn count_lines(text: &str) -> u64 {
+    Wordlist::from_text(text.to_string()).count_lines().unwrap()
+}
+
+#[test]
+fn line_endings() {
+    let text = "password\r\nhashcat!\n\n$HEX[00ff0a]\nlast";
+    assert_eq!(
+        candidates(text),
+        [&b"password"[..], b"hashcat!", b"", b"\x00\xff\n", b"last"]
+    );
+    assert_eq!(count_lines(text), 5);
+}
+
+/// Lines longer than [`MAX_LINE_LEN`] are skipped, however long they get.
+#[test]
+fn long_lines() {
+    let longest = "a".repeat(MAX_LINE_LEN - 1);
+    let too_long = "b".repeat(MAX_LINE_LEN);
+    let huge = "c".repeat(10 * 1024 * 1024);
+    let text = format!(
+        "first\n{}\n{}\n{}\r\nlast\n{}",
+        longest, too_long, huge, longest
+    );
+    assert_eq!(
+        candidates(&text),
+        [b"first", longest.as_bytes(), b"last", longest.as_bytes()]
+    );
+    assert_eq!(count_lines(&text), 4);
+
+    // A whole batch of long lines doesn't end the list early
+    let text = format!("{}\n", too_long).repeat(5000) + "last\n";
+    assert_eq!(candidates(&text), [b"last"]);
+    assert_eq!(count_lines(&text), 1);
+    // Without a newline, the last line can be a byte longer
+    assert_eq!(candidates(&too_long), [too_long.as_bytes()]);
+    assert_eq!(candidates(&format!("{}b", too_long)), Vec::<Vec<u8>>::new());
+}
+
+#[test]
+fn count_cancelled() {
+    let cancel = CancelToken::new();
+    let mut wordlist = Wordlist::from_text(String::from("one\ntwo\n"));
+    cancel.cancel();
+    assert_eq!(
+        wordlist.count_lines_until(&cancel).unwrap_err().kind(),
+        io::ErrorKind::Interrupted
+    );
+    assert_eq!(wordlist.count_lines().unwrap(), 2);
+}